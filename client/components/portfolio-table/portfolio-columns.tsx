@@ -4,15 +4,7 @@ import position_repay_rtm from "@/lib/manifests/position_repay";
 import position_withdraw_rtm from "@/lib/manifests/position_withdraw";
 import { bn, m_bn, math, num, round_dec } from "@/lib/math";
 import { gatewayApi, rdt } from "@/lib/radix";
-import {
-  Asset,
-  AssetName,
-  ammountToSupplyUnits,
-  getAssetAPR,
-  getAssetIcon,
-  supplyUnitsToAmount,
-  getUnitBalance,
-} from "@/types/asset";
+import { Asset, AssetName, getAssetAPR, getAssetIcon } from "@/types/asset";
 import { ColumnDef } from "@tanstack/react-table";
 import { BigNumber } from "mathjs";
 import { useState } from "react";
@@ -68,47 +60,8 @@ function ActionCell({
         (fr: { resource_address: string }) => fr.resource_address === borrowerBadgeAddr,
       )?.vaults.items[0];
 
-      const supplyUnitBalance = await getUnitBalance(accounts[0].address, row.original.label as AssetName);
-
-      console.log("row", row.original.address);
-      console.log("Native: ", amount.toString());
-      console.log("Wallet balance:", supplyUnitBalance.toString());
-
-      // Errors if the user doesn't have any supply units, or if the promise returns -1, which means there was an error
-      if (math.smallerEq(supplyUnitBalance, 0)) {
-        toast({
-          variant: "destructive",
-          title: "Withdrawal Failed",
-          description: "supplyUnitBalanceError",
-        });
-        return;
-      }
-
-      // Calculate how many supply units will be passed to the tx
-      const supplyRecord: Record<AssetName, BigNumber> = {
-        [row.original.label]: amount,
-      } as Record<AssetName, BigNumber>;
-
-      let supplyUnits = m_bn(math.multiply(await ammountToSupplyUnits(supplyRecord), slippageMultiplier));
-      supplyUnits = m_bn(math.min(supplyUnits, supplyUnitBalance));
-
-      let supplyRequested = "None";
-      if (!math.equal(supplyUnits, supplyUnitBalance)) {
-        // Value of the supply units un-adjusted for slipapge
-        // ! Removed due to causing lower-than-expected estimation
-        // const supplyUnitValue = math.multiply(
-        //   await supplyUnitsToAmount({ [row.original.label]: supplyUnits } as Record<AssetName, BigNumber>),
-        //   math.subtract(2, slippageMultiplier),
-        // );
-
-        const supplyUnitValue = await supplyUnitsToAmount({ [row.original.label]: supplyUnits } as Record<
-          AssetName,
-          BigNumber
-        >);
-
-        console.log("Supply unit value:", supplyUnitValue.toString());
-        supplyRequested = math.min(amount, m_bn(supplyUnitValue)).toString();
-      }
+      // Supply units are held in custody for the position; withdraw the full supply if all of it is requested
+      const supplyRequested = math.largerEq(amount, row.original.select_native) ? "None" : round_dec(amount).toString();
 
       if (!getNFTBalance?.items?.[0]) {
         toast({
@@ -124,11 +77,8 @@ function ActionCell({
         account: accounts[0].address,
         position_badge_address: borrowerBadgeAddr,
         position_badge_local_id: getNFTBalance.items[0],
-        asset: {
-          address: row.original.pool_unit_address ?? "",
-          amount: round_dec(supplyUnits).toString(),
-        },
-        requested: supplyRequested.toString(),
+        asset: row.original.address,
+        requested: supplyRequested,
       });
 
       console.log("Manifest: ", manifest);
@@ -3,15 +3,7 @@ import { Dialog, DialogContent, DialogHeader, DialogTitle } from "@/components/u
 import { Input } from "@/components/ui/input";
 import { TruncatedNumber } from "@/components/ui/truncated-number";
 import { bn, m_bn, math, num, round_dec } from "@/lib/math";
-import {
-  ammountToSupplyUnits,
-  Asset,
-  AssetName,
-  getAssetIcon,
-  getAssetPrice,
-  getUnitBalance,
-  supplyUnitsToAmount,
-} from "@/types/asset";
+import { Asset, AssetName, getAssetIcon, getAssetPrice } from "@/types/asset";
 import { ArrowRight, X } from "lucide-react";
 import { BigNumber } from "mathjs";
 import React, { useEffect, useState } from "react";
@@ -150,41 +142,15 @@ export function WithdrawDialog({
 
       const amount = bn(tempAmount);
 
-      const supplyUnitBalance = await getUnitBalance(accounts[0].address, asset.label);
-
-      const supplyRecord: Record<AssetName, BigNumber> = {
-        [asset.label]: amount,
-      } as Record<AssetName, BigNumber>;
-      const rawSupplyUnits = await ammountToSupplyUnits(supplyRecord);
-
-      let supplyUnits = m_bn(math.multiply(rawSupplyUnits, 1 + slippage / 100));
-      supplyUnits = m_bn(math.min(supplyUnits, supplyUnitBalance));
-
-      let supplyRequested = "None";
-      if (!math.equal(supplyUnits, supplyUnitBalance)) {
-        // Value of the supply units un-adjusted for slipapge
-        // ! Removed due to causing lower-than-expected estimation
-        // const supplyUnitValue = math.multiply(
-        //   await supplyUnitsToAmount({ [row.original.label]: supplyUnits } as Record<AssetName, BigNumber>),
-        //   math.subtract(2, slippageMultiplier),
-        // );
-
-        const supplyUnitValue = await supplyUnitsToAmount({ [asset.label]: supplyUnits } as Record<
-          AssetName,
-          BigNumber
-        >);
-        supplyRequested = round_dec(math.min(amount, m_bn(supplyUnitValue))).toString();
-      }
+      // Supply units are held in custody for the position; withdraw the full supply if all of it is requested
+      const supplyRequested = math.largerEq(amount, asset.select_native) ? "None" : round_dec(amount).toString();
 
       const previewManifest = position_withdraw_rtm({
         component: config.marketComponent,
         account: accounts[0].address,
         position_badge_address: nftInfo.address,
         position_badge_local_id: nftInfo.localId,
-        asset: {
-          address: asset.pool_unit_address,
-          amount: round_dec(supplyUnits).toString(),
-        },
+        asset: asset.address,
         requested: supplyRequested,
       });
 
@@ -6,8 +6,11 @@ interface ManifestArgs {
   position_badge_local_id: string; // e.g. #1#
 
   assets: Asset[]; // tracked assets
+  mode?: BorrowMode; // defaults to Variable
 }
 
+type BorrowMode = "Variable" | "Fixed";
+
 interface Asset {
   address: string;
   amount: string;
@@ -19,6 +22,7 @@ export default function position_borrow_rtm({
   position_badge_address,
   position_badge_local_id,
   assets,
+  mode = "Variable",
 }: ManifestArgs) {
   // Bucket fetch transaction manifests
   let asset_entry = "";
@@ -58,7 +62,8 @@ CALL_METHOD
   "position_borrow"
   Proof("position_proof")
   Map<Address, Decimal>(${asset_entry}
-  );
+  )
+  Enum<${mode === "Fixed" ? 1 : 0}u8>();
 
 ${asset_deposit}
 `;
@@ -7,8 +7,11 @@ interface ManifestArgs {
 
   asset: Asset; // tracked asset
   requested: string;
+  mode?: BorrowMode; // defaults to Variable
 }
 
+type BorrowMode = "Variable" | "Fixed";
+
 interface Asset {
   address: string;
   amount: string;
@@ -21,6 +24,7 @@ export default function position_repay_rtm({
   position_badge_local_id,
   asset,
   requested,
+  mode = "Variable",
 }: ManifestArgs) {
   const req = requested === "None" ? "None" : `Some(Decimal("${requested}"))`;
 
@@ -50,7 +54,8 @@ CALL_METHOD
   "position_repay"
   Proof("position_proof")
   Bucket("bucket_1")
-  ${req};
+  ${req}
+  Enum<${mode === "Fixed" ? 1 : 0}u8>();
 
 CALL_METHOD
   Address("${account}")
@@ -5,13 +5,8 @@ interface ManifestArgs {
   position_badge_address: string; // resource_...
   position_badge_local_id: string; // e.g. #1#
 
-  asset: Asset; // supply units
-  requested: string; // amount
-}
-
-interface Asset {
-  address: string;
-  amount: string;
+  asset: string; // resource_... of the supplied asset
+  requested: string; // amount, or "None" to withdraw the full supply
 }
 
 export default function position_repay_rtm({
@@ -34,22 +29,11 @@ CALL_METHOD
 POP_FROM_AUTH_ZONE
     Proof("position_proof");
 
-CALL_METHOD
-  Address("${account}")
-  "withdraw"
-  Address("${asset.address}")
-  Decimal("${asset.amount}");
-
-TAKE_FROM_WORKTOP
-  Address("${asset.address}")
-  Decimal("${asset.amount}")
-  Bucket("bucket_1");
-
 CALL_METHOD
   Address("${component}")
   "position_withdraw"
   Proof("position_proof")
-  Bucket("bucket_1")
+  Address("${asset}")
   ${req};
 
 CALL_METHOD
@@ -1,15 +1,29 @@
 /* ------------------ Imports ----------------- */
+use crate::events::*;
 use crate::utils::*;
+pub use lattic3_interest_curve::InterestRateModel;
 use scrypto::prelude::*;
+use scrypto_avltree::AvlTree;
 
 /* ---------------- Structures ---------------- */
-#[derive(ScryptoSbor, Debug, Clone)]
+#[derive(ScryptoSbor, ManifestSbor, Debug, Clone)]
 pub struct ClusterState {
     pub at: i64, // seconds
 
     pub resource: ResourceAddress,
+    pub authority: ResourceAddress,
     pub supply_unit: ResourceAddress,
+    pub debt_unit: ResourceAddress,
+    pub flash_loan_receipt: ResourceAddress,
+    pub fixed_rate_loan: ResourceAddress,
+    pub withdrawal_claim: ResourceAddress,
     pub liquidity: Decimal,
+    pub flash_loaned: Decimal,
+    pub provided_liquidity: PreciseDecimal,
+
+    pub queued_units: Decimal,
+    pub claimable: Decimal,
+    pub withdrawal_claims: u64,
 
     pub supply: PreciseDecimal,
     pub supply_units: PreciseDecimal,
@@ -21,18 +35,276 @@ pub struct ClusterState {
     pub virtual_debt: PreciseDecimal,
     pub debt_ratio: PreciseDecimal,
 
+    pub fixed_debt: PreciseDecimal,
+    pub fixed_debt_units: PreciseDecimal,
+    pub virtual_fixed_debt: PreciseDecimal,
+    pub fixed_rate: PreciseDecimal,
+    pub fixed_loans: u64,
+    pub fixed_rate_premium: PreciseDecimal,
+    pub fixed_rebalance_threshold: PreciseDecimal,
+
+    pub utilization: PreciseDecimal,
     pub apr: PreciseDecimal,
+    pub supply_apr: PreciseDecimal,
     pub apr_ticked: i64, // seconds
+
+    pub interest_model: InterestRateModel,
+    pub interest_model_address: Option<ComponentAddress>,
+    pub interest_tick_interval: i64, // seconds
+    pub keeper_reward: Decimal,
+
+    pub reserves: PreciseDecimal,
+    pub reserve_factor: PreciseDecimal,
+    pub flash_loan_fee: PreciseDecimal,
+
+    pub supply_cap: Option<Decimal>,
+    pub borrow_cap: Option<Decimal>,
+
+    pub status: ClusterStatus,
+    pub operations: ClusterOperations,
+}
+
+/// Result of checking a cluster's internal invariants; see `Cluster::verify_state`.
+#[derive(ScryptoSbor, Debug, Clone)]
+pub struct ClusterStateReport {
+    pub at: i64, // seconds
+
+    pub valid: bool,
+    pub violations: Vec<String>,
+}
+
+/// Transient receipt of a flash loan; it cannot be deposited, so the loan must be repaid within the same transaction.
+#[derive(ScryptoSbor, NonFungibleData, Debug, Clone)]
+pub struct FlashLoanReceipt {
+    pub amount: Decimal, // Borrowed amount
+    pub fee: Decimal,    // Fee owed on top of the borrowed amount
+}
+
+/// Fixed-rate loan, whose rate is locked when borrowing and only changes on top-ups or rebalancing.
+#[derive(ScryptoSbor, NonFungibleData, Debug, Clone)]
+pub struct FixedRateLoan {
+    #[mutable]
+    pub principal: Decimal, // Amount owed as of `accrued_at`
+    #[mutable]
+    pub rate: PreciseDecimal, // Locked borrow APR
+    #[mutable]
+    pub accrued_at: i64, // seconds
+    #[mutable]
+    pub units: PreciseDecimal, // Share of the cluster's virtual fixed-rate debt
 }
 
+/// Claim on a queued withdrawal; supply units are redeemed into it as liquidity becomes available.
+#[derive(ScryptoSbor, NonFungibleData, Debug, Clone)]
+pub struct WithdrawalClaim {
+    #[mutable]
+    pub units: Decimal, // Supply units still waiting in the queue
+    #[mutable]
+    pub filled: Decimal, // Resource set aside for the claim, ready to be redeemed
+    pub queue_index: u64, // Position of the request in the withdrawal queue
+}
+
+/// Rates and exchange ratios of a cluster at a point in time, recorded after operations at most once per tick interval.
 #[derive(ScryptoSbor, Debug, Clone)]
+pub struct ClusterSnapshot {
+    pub at: i64, // seconds
+
+    pub supply_ratio: PreciseDecimal,
+    pub debt_ratio: PreciseDecimal,
+
+    pub borrow_rate: PreciseDecimal,
+    pub supply_rate: PreciseDecimal,
+    pub utilization: PreciseDecimal,
+}
+
+#[derive(ScryptoSbor, ManifestSbor, Debug, Clone)]
 pub enum ClusterLayer {
     Supply,
     Debt,
 }
 
+/// Direction in which a conversion between resource amounts and units is rounded; it must always favor the cluster.
+#[derive(ScryptoSbor, ManifestSbor, Debug, Clone, Copy, PartialEq)]
+pub enum Rounding {
+    Down,
+    Up,
+}
+
+impl Rounding {
+    /// Rounds `amount` to a `Decimal` in this direction.
+    pub fn apply(&self, amount: PreciseDecimal) -> Decimal {
+        match self {
+            Rounding::Down => round_down(amount),
+            Rounding::Up => round_up(amount),
+        }
+    }
+}
+
+/// Operational status of a cluster, used for incident response.
+#[derive(ScryptoSbor, ManifestSbor, Debug, Clone, PartialEq)]
+pub enum ClusterStatus {
+    Active, // All operations allowed
+    Frozen, // No new supply or borrows; withdrawals and repayments allowed
+    Paused, // Only repayments allowed
+}
+
+#[derive(ScryptoSbor, ManifestSbor, Debug, Clone)]
+pub enum ClusterOperation {
+    Supply,
+    Withdraw,
+    Borrow,
+    Repay,
+}
+
+/// Per-operation toggles, applied on top of the cluster's `ClusterStatus`.
+#[derive(ScryptoSbor, ManifestSbor, Debug, Clone)]
+pub struct ClusterOperations {
+    pub supply: bool,
+    pub withdraw: bool,
+    pub borrow: bool,
+    pub repay: bool,
+}
+
+impl Default for ClusterOperations {
+    /// Returns toggles with every operation enabled.
+    fn default() -> Self {
+        ClusterOperations { supply: true, withdraw: true, borrow: true, repay: true }
+    }
+}
+
+impl ClusterOperations {
+    /// Returns whether the given operation is allowed under both the toggles and the cluster status.
+    pub fn is_allowed(&self, operation: &ClusterOperation, status: &ClusterStatus) -> bool {
+        let allowed_by_status = match status {
+            ClusterStatus::Active => true,
+            ClusterStatus::Frozen => matches!(operation, ClusterOperation::Withdraw | ClusterOperation::Repay),
+            ClusterStatus::Paused => matches!(operation, ClusterOperation::Repay),
+        };
+
+        let enabled = match operation {
+            ClusterOperation::Supply => self.supply,
+            ClusterOperation::Withdraw => self.withdraw,
+            ClusterOperation::Borrow => self.borrow,
+            ClusterOperation::Repay => self.repay,
+        };
+
+        allowed_by_status && enabled
+    }
+}
+
+/// Configuration of a new cluster; see `Cluster::instantiate`.
+#[derive(ScryptoSbor, ManifestSbor, Debug, Clone)]
+pub struct ClusterConfig {
+    pub interest_model: InterestRateModel,
+    pub reserve_factor: PreciseDecimal,
+    pub flash_loan_fee: PreciseDecimal,
+    pub fixed_rate_premium: PreciseDecimal,
+    pub fixed_rebalance_threshold: PreciseDecimal,
+
+    pub supply_cap: Option<Decimal>,
+    pub borrow_cap: Option<Decimal>,
+
+    pub interest_tick_interval: i64, // seconds
+    pub keeper_reward: Decimal,
+
+    pub unit_name: Option<String>, // Overrides the default "Lattic3 {name}" supply unit name
+    pub unit_symbol: Option<String>, // Overrides the default "$lt3{symbol}" supply unit symbol
+    pub unit_icon_url: Option<String>, // Icon of the supply unit, if any
+
+    pub status: ClusterStatus, // Initial status of the cluster
+}
+
+impl Default for ClusterConfig {
+    /// Returns the default configuration: the default interest rate curve and fees, no caps, and an active cluster.
+    fn default() -> Self {
+        ClusterConfig {
+            interest_model: InterestRateModel::default(),
+            reserve_factor: DEFAULT_RESERVE_FACTOR,
+            flash_loan_fee: DEFAULT_FLASH_LOAN_FEE,
+            fixed_rate_premium: DEFAULT_FIXED_RATE_PREMIUM,
+            fixed_rebalance_threshold: DEFAULT_FIXED_REBALANCE_THRESHOLD,
+
+            supply_cap: None,
+            borrow_cap: None,
+
+            interest_tick_interval: DEFAULT_INTEREST_TICK_INTERVAL,
+            keeper_reward: DEFAULT_KEEPER_REWARD,
+
+            unit_name: None,
+            unit_symbol: None,
+            unit_icon_url: None,
+
+            status: ClusterStatus::Active,
+        }
+    }
+}
+
+impl ClusterConfig {
+    /// Asserts that the configuration is within the same bounds enforced by the cluster's setters.
+    ///
+    /// # Panics
+    /// * If the interest rate model is invalid.
+    /// * If the reserve factor is not within [0, 1), or the flash loan fee is not within [0, 1].
+    /// * If the fixed rate premium, rebalance threshold, a cap or the keeper reward is negative.
+    /// * If the interest tick interval is not greater than zero.
+    /// * If a supply unit name, symbol or icon override is empty.
+    pub fn validate(&self) {
+        self.interest_model.validate();
+
+        assert!(
+            self.reserve_factor >= pdec!(0) && self.reserve_factor < pdec!(1),
+            "Reserve factor must be between 0 (inclusive) and 1 (exclusive)"
+        );
+        assert!(
+            self.flash_loan_fee >= pdec!(0) && self.flash_loan_fee <= pdec!(1),
+            "Flash loan fee must be between 0 and 1 (inclusive)"
+        );
+        assert!(
+            self.fixed_rate_premium >= pdec!(0),
+            "Fixed rate premium must be non-negative"
+        );
+        assert!(
+            self.fixed_rebalance_threshold >= pdec!(0),
+            "Fixed rebalance threshold must be non-negative"
+        );
+
+        if let Some(cap) = self.supply_cap {
+            assert!(cap >= dec!(0), "Supply cap must be non-negative");
+        }
+        if let Some(cap) = self.borrow_cap {
+            assert!(cap >= dec!(0), "Borrow cap must be non-negative");
+        }
+
+        assert!(
+            self.interest_tick_interval > 0,
+            "Interest tick interval must be greater than zero"
+        );
+        assert!(self.keeper_reward >= dec!(0), "Keeper reward must not be negative");
+
+        for (field, value) in [
+            ("name", &self.unit_name),
+            ("symbol", &self.unit_symbol),
+            ("icon URL", &self.unit_icon_url),
+        ] {
+            if let Some(value) = value {
+                assert!(!value.is_empty(), "Supply unit {} must not be empty", field);
+            }
+        }
+    }
+}
+
 /* ------------------ Cluster ----------------- */
 #[blueprint]
+#[events(
+    ClusterSupplyEvent,
+    ClusterWithdrawEvent,
+    ClusterBorrowEvent,
+    ClusterRepayEvent,
+    LiquidityProvidedEvent,
+    LiquidityWithdrawnEvent,
+    InterestTickEvent,
+    KeeperRewardEvent,
+    BadDebtAbsorbedEvent
+)]
 mod lattic3_cluster {
     //] --------------- Scrypto Setup -------------- /
 
@@ -46,16 +318,53 @@ mod lattic3_cluster {
             withdraw => restrict_to: [OWNER, admin];
             repay    => restrict_to: [OWNER, admin];
 
+            borrow_fixed    => restrict_to: [OWNER, admin];
+            repay_fixed     => restrict_to: [OWNER, admin];
+            rebalance_fixed => restrict_to: [OWNER, admin];
+
+            absorb_bad_debt       => restrict_to: [OWNER, admin];
+            absorb_bad_fixed_debt => restrict_to: [OWNER, admin];
+
+            request_withdrawal    => restrict_to: [OWNER, admin];
+            claim_withdrawal      => PUBLIC;
+            cancel_withdrawal     => restrict_to: [OWNER, admin];
+            fill_withdrawal_queue => PUBLIC;
+
+            flash_borrow => PUBLIC;
+            flash_repay  => PUBLIC;
+
             get_ratio         => PUBLIC;
             get_amount        => PUBLIC;
+            preview_amount    => PUBLIC;
             get_units         => PUBLIC;
+            get_fixed_debt    => PUBLIC;
+            get_fixed_rate    => PUBLIC;
             get_cluster_state => PUBLIC;
+            verify_state      => PUBLIC;
+            get_history       => PUBLIC;
 
             provide_liquidity  => restrict_to: [OWNER, admin];
             withdraw_liquidity => restrict_to: [OWNER];
+            collect_reserves   => restrict_to: [OWNER];
+            export_state       => restrict_to: [OWNER];
 
             tick_interest              => PUBLIC;
             set_interest_tick_interval => restrict_to: [OWNER, admin];
+            set_keeper_reward          => restrict_to: [OWNER, admin];
+            set_interest_model         => restrict_to: [OWNER, admin];
+            link_interest_model        => restrict_to: [OWNER, admin];
+            unlink_interest_model      => restrict_to: [OWNER, admin];
+            set_reserve_factor         => restrict_to: [OWNER, admin];
+            set_flash_loan_fee         => restrict_to: [OWNER, admin];
+
+            set_fixed_rate_premium        => restrict_to: [OWNER, admin];
+            set_fixed_rebalance_threshold => restrict_to: [OWNER, admin];
+
+            set_supply_cap => restrict_to: [OWNER, admin];
+            set_borrow_cap => restrict_to: [OWNER, admin];
+
+            set_status            => restrict_to: [OWNER, admin];
+            set_operation_enabled => restrict_to: [OWNER, admin];
         }
     }
 
@@ -65,8 +374,25 @@ mod lattic3_cluster {
         component: ComponentAddress, // Address of the cluster component
 
         resource: ResourceAddress,            // Resource that the cluster contains
+        authority: FungibleVault,             // Badge authorizing minting and burning of units
         supply_unit_manager: ResourceManager, // Manager for the supply units
+        debt_unit_manager: ResourceManager,   // Manager for the (non-withdrawable) debt units
+        flash_loan_manager: ResourceManager,  // Manager for the (non-depositable) flash loan receipts
+        fixed_loan_manager: ResourceManager,  // Manager for the (non-withdrawable) fixed-rate loans
+        claim_manager: ResourceManager,       // Manager for the withdrawal claims
         liquidity: Vault,                     // Vault that holds liquidity
+        dead_units: Vault,                    // Supply units locked forever on the first supply
+        queued_units: Vault,                  // Supply units locked in queued withdrawal requests
+        claimable: Vault,                     // Resource redeemed for withdrawal claims, awaiting collection
+
+        withdrawal_queue: KeyValueStore<u64, NonFungibleLocalId>, // Queued withdrawal claims, in FIFO order
+        queue_head: u64,                                          // Index of the oldest queued claim
+        queue_tail: u64,                                          // Index at which the next claim is queued
+        withdrawal_claims: u64,                                   // Number of outstanding withdrawal claims
+
+        flash_loaned: Decimal, // Liquidity currently lent out through flash loans
+
+        provided_liquidity: PreciseDecimal, // Liquidity provided outside of supply, see `provide_liquidity`
 
         supply: PreciseDecimal,         // Raw supply value, equivalent to liquidity + debt
         supply_units: PreciseDecimal,   // Number of supply units issued
@@ -76,22 +402,53 @@ mod lattic3_cluster {
         debt_units: PreciseDecimal,   // Number of debt units issued
         virtual_debt: PreciseDecimal, // Adjustable value of the supply units
 
-        apr: PreciseDecimal, // The interest rate, updated at interest_tick_interval
-        apr_ticked: i64,     // Last time the interest rate was ticked
+        fixed_debt: PreciseDecimal,         // Raw fixed-rate debt value
+        fixed_debt_units: PreciseDecimal,   // Shares of the virtual fixed-rate debt, held by the loans
+        virtual_fixed_debt: PreciseDecimal, // Value owed on all fixed-rate loans
+        fixed_rate: PreciseDecimal,         // Average rate of the fixed-rate loans, weighted by value owed
+        fixed_loans: u64,                   // Number of open fixed-rate loans
+
+        fixed_rate_premium: PreciseDecimal, // Premium over the borrow rate charged on new fixed-rate loans
+        fixed_rebalance_threshold: PreciseDecimal, // Rate divergence above which a fixed-rate loan can be rebalanced
+
+        interest_model: InterestRateModel, // Built-in interest rate curve
+        interest_model_address: Option<ComponentAddress>, // Linked InterestModel, if any
+
+        apr: PreciseDecimal,        // The borrow interest rate, updated at interest_tick_interval
+        supply_apr: PreciseDecimal, // The supply interest rate, updated at interest_tick_interval
+        apr_ticked: i64,            // Last time the interest rate was ticked
+
+        reserves: PreciseDecimal,       // Interest accrued to the protocol
+        reserve_factor: PreciseDecimal, // Share of supply interest withheld
+        flash_loan_fee: PreciseDecimal, // Share of flash loans charged as a fee
 
-        // price_update_interval: i64,  // Interval (in minutes) between price updates
-        interest_tick_interval: i64, // Interval (in minutes) between interest ticks
+        supply_cap: Option<Decimal>, // Maximum virtual supply, if limited
+        borrow_cap: Option<Decimal>, // Maximum virtual debt (variable and fixed-rate), if limited
+
+        status: ClusterStatus,         // Operational status of the cluster
+        operations: ClusterOperations, // Per-operation toggles
+
+        history: AvlTree<i64, ClusterSnapshot>, // Snapshots recorded after operations, keyed by timestamp
+        history_length: u64,                    // Number of snapshots in the history
+        history_start: i64,                     // Timestamp of the oldest snapshot in the history
+        history_end: i64,                       // Timestamp of the newest snapshot in the history
+
+        // price_update_interval: i64,  // Interval (in seconds) between price updates
+        interest_tick_interval: i64, // Interval (in seconds) between non-forced interest ticks
+        keeper_reward: Decimal,      // Reserves paid to the caller of a due, non-forced interest tick
     }
 
     impl Cluster {
-        /// Instantiates a new `Cluster` component with the given resource and access rules.
+        /// Instantiates a new `Cluster` component with the given resource, configuration and access rules.
         ///
         /// Sets up a new `Cluster` component for the given resource and access rules.
-        /// The metadata for the supply unit is set from the metadata of the provided resource.
+        /// The metadata for the supply unit is set from the metadata of the provided resource, unless overridden by the
+        /// configuration.
         /// `apr_ticked` is set to the current time to prevent incorrect interest tick intervals.
         ///
         /// # Parameters
         /// * `resource`: The `ResourceAddress` of the resource that the cluster will manage. It must be a fungible resource.
+        /// * `config`: The `ClusterConfig` of the cluster. If not provided, `ClusterConfig::default()` is used.
         /// * `cluster_owner_rule`: An `AccessRule` that defines the owner of the cluster.
         /// * `cluster_admin_rule`: An `AccessRule` that defines the admin of the cluster.
         ///
@@ -100,8 +457,10 @@ mod lattic3_cluster {
         ///
         /// # Panics
         /// * If the provided resource is invalid, or if the metadata cannot be set.
+        /// * If the configuration is invalid; see `ClusterConfig::validate`.
         pub fn instantiate(
             resource: ResourceAddress,
+            config: Option<ClusterConfig>,
             cluster_owner_rule: AccessRule,
             cluster_admin_rule: AccessRule,
         ) -> Global<Cluster> {
@@ -116,23 +475,80 @@ mod lattic3_cluster {
                 resource
             );
 
-            let resource_name: String = resource_manager
-                .get_metadata("name")
-                .expect(format!("Couldn't get metadata (name) for {:?}", resource).as_str())
-                .expect(format!("Metadata (name) for {:?} was none", resource).as_str());
-            let resource_symbol: String = resource_manager
-                .get_metadata("symbol")
-                .expect(format!("Couldn't get metadata (symbol) for {:?}", resource).as_str())
-                .expect(format!("Metadata (symbol) for {:?} was none", resource).as_str());
+            let config = config.unwrap_or_default();
+            config.validate();
 
-            //] Authorization
-            let component_access_rule = rule!(require(global_caller(component_address)));
+            let (resource_name, resource_symbol) = Self::__resource_metadata(resource);
 
+            //] Authorization
             let cluster_owner = OwnerRole::Fixed(cluster_owner_rule);
 
+            // Setup authority badge; minting and burning units requires it, so it can be handed over on migration
+            let authority = ResourceBuilder::new_fungible(cluster_owner.clone())
+                .metadata(metadata! {
+                    roles {
+                        metadata_setter         => OWNER;
+                        metadata_setter_updater => OWNER;
+                        metadata_locker         => OWNER;
+                        metadata_locker_updater => rule!(deny_all);
+                    },
+                    init {
+                        "name" => format!("Lattic3 {} Cluster Authority", resource_name), locked;
+                    }
+                })
+                .divisibility(DIVISIBILITY_NONE)
+                .mint_initial_supply(1);
+            let authority_rule = rule!(require(authority.resource_address()));
+
             //] Internal state setup
             // Setup supply unit
+            let unit_name = config
+                .unit_name
+                .clone()
+                .unwrap_or_else(|| format!("Lattic3 {}", resource_name));
+            let unit_symbol = config
+                .unit_symbol
+                .clone()
+                .unwrap_or_else(|| format!("$lt3{}", resource_symbol));
+
+            let mut unit_metadata = metadata! {
+                roles {
+                    metadata_setter         => OWNER;
+                    metadata_setter_updater => OWNER;
+                    metadata_locker         => OWNER;
+                    metadata_locker_updater => rule!(deny_all);
+                },
+                init {
+                    "name"   => unit_name, locked;
+                    "symbol" => unit_symbol, locked;
+                }
+            };
+            if let Some(icon_url) = config.unit_icon_url.clone() {
+                unit_metadata
+                    .init
+                    .set_and_lock_metadata("icon_url", UncheckedUrl::of(icon_url));
+            }
+
             let supply_unit_manager = ResourceBuilder::new_fungible(cluster_owner.clone())
+                .metadata(unit_metadata)
+                .divisibility(DIVISIBILITY_MAXIMUM)
+                .burn_roles(burn_roles! {
+                    burner         => authority_rule.clone();
+                    burner_updater => rule!(deny_all);
+                })
+                .mint_roles(mint_roles! {
+                    minter         => authority_rule.clone();
+                    minter_updater => rule!(deny_all);
+                })
+                // ! May want to remove recall_roles; currently hypothesised to be used for liquidation
+                .recall_roles(recall_roles! {
+                    recaller         => authority_rule.clone();
+                    recaller_updater => rule!(deny_all);
+                })
+                .create_with_no_initial_supply();
+
+            // Setup debt unit; only the cluster admin (i.e. the market) can move debt units once deposited
+            let debt_unit_manager = ResourceBuilder::new_fungible(cluster_owner.clone())
                 .metadata(metadata! {
                     roles {
                         metadata_setter         => OWNER;
@@ -141,32 +557,125 @@ mod lattic3_cluster {
                         metadata_locker_updater => rule!(deny_all);
                     },
                     init {
-                        "name"   => format!("Lattic3 {}", resource_name), locked;
-                        "symbol" => format!("$lt3{}", resource_symbol), locked;
+                        "name"   => format!("Lattic3 {} Debt", resource_name), locked;
+                        "symbol" => format!("$lt3d{}", resource_symbol), locked;
                     }
                 })
                 .divisibility(DIVISIBILITY_MAXIMUM)
                 .burn_roles(burn_roles! {
-                    burner         => component_access_rule.clone();
+                    burner         => authority_rule.clone();
                     burner_updater => rule!(deny_all);
                 })
                 .mint_roles(mint_roles! {
-                    minter         => component_access_rule.clone();
+                    minter         => authority_rule.clone();
                     minter_updater => rule!(deny_all);
                 })
-                // ! May want to remove recall_roles; currently hypothesised to be used for liquidation
+                .withdraw_roles(withdraw_roles! {
+                    withdrawer         => cluster_admin_rule.clone();
+                    withdrawer_updater => rule!(deny_all);
+                })
                 .recall_roles(recall_roles! {
-                    recaller         => component_access_rule;
+                    recaller         => authority_rule.clone();
                     recaller_updater => rule!(deny_all);
                 })
                 .create_with_no_initial_supply();
 
+            // Setup fixed-rate loans; like debt units, only the cluster admin can move them once deposited
+            let fixed_loan_manager: ResourceManager =
+                ResourceBuilder::new_ruid_non_fungible::<FixedRateLoan>(cluster_owner.clone())
+                    .metadata(metadata! {
+                        roles {
+                            metadata_setter         => OWNER;
+                            metadata_setter_updater => OWNER;
+                            metadata_locker         => OWNER;
+                            metadata_locker_updater => rule!(deny_all);
+                        },
+                        init {
+                            "name" => format!("Lattic3 {} Fixed Debt", resource_name), locked;
+                        }
+                    })
+                    .burn_roles(burn_roles! {
+                        burner         => authority_rule.clone();
+                        burner_updater => rule!(deny_all);
+                    })
+                    .mint_roles(mint_roles! {
+                        minter         => authority_rule.clone();
+                        minter_updater => rule!(deny_all);
+                    })
+                    .non_fungible_data_update_roles(non_fungible_data_update_roles! {
+                        non_fungible_data_updater         => authority_rule.clone();
+                        non_fungible_data_updater_updater => rule!(deny_all);
+                    })
+                    .withdraw_roles(withdraw_roles! {
+                        withdrawer         => cluster_admin_rule.clone();
+                        withdrawer_updater => rule!(deny_all);
+                    })
+                    .recall_roles(recall_roles! {
+                        recaller         => authority_rule;
+                        recaller_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply()
+                    .into();
+
+            // Setup withdrawal claims
+            let claim_manager: ResourceManager =
+                ResourceBuilder::new_ruid_non_fungible::<WithdrawalClaim>(cluster_owner.clone())
+                    .metadata(metadata! {
+                        roles {
+                            metadata_setter         => OWNER;
+                            metadata_setter_updater => OWNER;
+                            metadata_locker         => OWNER;
+                            metadata_locker_updater => rule!(deny_all);
+                        },
+                        init {
+                            "name" => format!("Lattic3 {} Withdrawal Claim", resource_name), locked;
+                        }
+                    })
+                    .burn_roles(burn_roles! {
+                        burner         => authority_rule.clone();
+                        burner_updater => rule!(deny_all);
+                    })
+                    .mint_roles(mint_roles! {
+                        minter         => authority_rule.clone();
+                        minter_updater => rule!(deny_all);
+                    })
+                    .non_fungible_data_update_roles(non_fungible_data_update_roles! {
+                        non_fungible_data_updater         => authority_rule.clone();
+                        non_fungible_data_updater_updater => rule!(deny_all);
+                    })
+                    .create_with_no_initial_supply()
+                    .into();
+
+            let dead_units = Vault::new(supply_unit_manager.address());
+            let queued_units = Vault::new(supply_unit_manager.address());
+
             let component_state = Cluster {
                 component: component_address,
 
                 resource,
+                authority: FungibleVault::with_bucket(authority),
                 supply_unit_manager,
+                debt_unit_manager,
+                flash_loan_manager: Self::__create_flash_loan_manager(
+                    component_address,
+                    &cluster_owner,
+                    &resource_name,
+                ),
+                fixed_loan_manager,
+                claim_manager,
                 liquidity: Vault::new(resource),
+                dead_units,
+                queued_units,
+                claimable: Vault::new(resource),
+
+                withdrawal_queue: KeyValueStore::new(),
+                queue_head: 0,
+                queue_tail: 0,
+                withdrawal_claims: 0,
+
+                flash_loaned: Decimal::zero(),
+
+                provided_liquidity: PreciseDecimal::zero(),
 
                 supply: PreciseDecimal::zero(),
                 supply_units: PreciseDecimal::zero(),
@@ -176,42 +685,188 @@ mod lattic3_cluster {
                 debt_units: PreciseDecimal::zero(),
                 virtual_debt: PreciseDecimal::zero(),
 
+                fixed_debt: PreciseDecimal::zero(),
+                fixed_debt_units: PreciseDecimal::zero(),
+                virtual_fixed_debt: PreciseDecimal::zero(),
+                fixed_rate: PreciseDecimal::zero(),
+                fixed_loans: 0,
+
+                fixed_rate_premium: config.fixed_rate_premium,
+                fixed_rebalance_threshold: config.fixed_rebalance_threshold,
+
+                interest_model: config.interest_model,
+                interest_model_address: None,
                 apr: PreciseDecimal::zero(),
+                supply_apr: PreciseDecimal::zero(),
                 apr_ticked: now(), // Set it to the time when the component is instantiated, since otherwise interest is assumed to have ticked last in 1970
 
-                interest_tick_interval: 2, // seconds // ! Change for prod
-            };
+                reserves: PreciseDecimal::zero(),
+                reserve_factor: config.reserve_factor,
+                flash_loan_fee: config.flash_loan_fee,
 
-            //] Instantiate the component
-            let name = format!("Lattic3 {} Cluster", resource_symbol);
-            let description = format!("Cluster for the Lattic3 lending platform. Holds {}", resource_name);
+                supply_cap: config.supply_cap,
+                borrow_cap: config.borrow_cap,
 
-            let component_metadata = metadata! {
-                roles {
-                    metadata_setter         => OWNER;
-                    metadata_setter_updater => OWNER;
-                    metadata_locker         => OWNER;
-                    metadata_locker_updater => rule!(deny_all);
-                },
-                init {
-                    "name"        => name, locked;
-                    "description" => description, locked;
-                }
+                status: config.status,
+                operations: ClusterOperations::default(),
+
+                history: AvlTree::new(),
+                history_length: 0,
+                history_start: now(),
+                history_end: now(),
+
+                interest_tick_interval: config.interest_tick_interval,
+                keeper_reward: config.keeper_reward,
             };
 
-            let component_roles = roles! {
-                admin => cluster_admin_rule;
+            Self::__globalize(
+                component_state,
+                address_reservation,
+                cluster_owner,
+                cluster_admin_rule,
+                resource_name,
+                resource_symbol,
+            )
+        }
+
+        /// Instantiates a `Cluster` component which takes over the state exported from another cluster.
+        ///
+        /// The supply unit, debt unit and fixed-rate loan resources of the exported cluster are kept, so lenders' supply
+        /// units and borrowers' debt units and loans remain valid. The rate history is not carried over.
+        ///
+        /// # Parameters
+        /// * `state`: The `ClusterState` returned by `export_state`.
+        /// * `liquidity`: A `Bucket` containing the exported cluster's liquidity.
+        /// * `authority`: A `FungibleBucket` containing the exported cluster's authority badge.
+        /// * `cluster_owner_rule`: An `AccessRule` that defines the owner of the cluster.
+        /// * `cluster_admin_rule`: An `AccessRule` that defines the admin of the cluster.
+        ///
+        /// # Returns
+        /// * A `Global<Cluster>` instance representing the newly created cluster component.
+        ///
+        /// # Panics
+        /// * If the liquidity or authority badge do not match the exported state.
+        /// * If the exported state is invalid.
+        pub fn instantiate_from_state(
+            state: ClusterState,
+            liquidity: Bucket,
+            authority: FungibleBucket,
+            cluster_owner_rule: AccessRule,
+            cluster_admin_rule: AccessRule,
+        ) -> Global<Cluster> {
+            // Reserve component address
+            let (address_reservation, component_address) = Runtime::allocate_component_address(Cluster::blueprint_id());
+
+            //] Sanity checks
+            assert!(
+                liquidity.resource_address() == state.resource,
+                "Liquidity must be of the exported resource {:?}",
+                state.resource
+            );
+            assert!(
+                liquidity.amount() == state.liquidity,
+                "Liquidity of {} does not match the exported {}",
+                liquidity.amount(),
+                state.liquidity
+            );
+            assert!(
+                authority.resource_address() == state.authority && authority.amount() == dec!(1),
+                "Authority badge does not match the exported cluster"
+            );
+            assert!(
+                state.flash_loaned == dec!(0),
+                "Cannot migrate a cluster with outstanding flash loans"
+            );
+            assert!(
+                state.withdrawal_claims == 0,
+                "Cannot migrate a cluster with outstanding withdrawal claims"
+            );
+
+            let (resource_name, resource_symbol) = Self::__resource_metadata(state.resource);
+
+            let cluster_owner = OwnerRole::Fixed(cluster_owner_rule);
+
+            //] Internal state setup
+            let component_state = Cluster {
+                component: component_address,
+
+                resource: state.resource,
+                authority: FungibleVault::with_bucket(authority),
+                supply_unit_manager: ResourceManager::from_address(state.supply_unit),
+                debt_unit_manager: ResourceManager::from_address(state.debt_unit),
+                flash_loan_manager: Self::__create_flash_loan_manager(
+                    component_address,
+                    &cluster_owner,
+                    &resource_name,
+                ),
+                fixed_loan_manager: ResourceManager::from_address(state.fixed_rate_loan),
+                claim_manager: ResourceManager::from_address(state.withdrawal_claim),
+                liquidity: Vault::with_bucket(liquidity),
+                dead_units: Vault::new(state.supply_unit), // The exported cluster's dead units stay locked within it
+                queued_units: Vault::new(state.supply_unit),
+                claimable: Vault::new(state.resource),
+
+                withdrawal_queue: KeyValueStore::new(),
+                queue_head: 0,
+                queue_tail: 0,
+                withdrawal_claims: 0,
+
+                flash_loaned: Decimal::zero(),
+
+                provided_liquidity: state.provided_liquidity,
+
+                supply: state.supply,
+                supply_units: state.supply_units,
+                virtual_supply: state.virtual_supply,
+
+                debt: state.debt,
+                debt_units: state.debt_units,
+                virtual_debt: state.virtual_debt,
+
+                fixed_debt: state.fixed_debt,
+                fixed_debt_units: state.fixed_debt_units,
+                virtual_fixed_debt: state.virtual_fixed_debt,
+                fixed_rate: state.fixed_rate,
+                fixed_loans: state.fixed_loans,
+
+                fixed_rate_premium: state.fixed_rate_premium,
+                fixed_rebalance_threshold: state.fixed_rebalance_threshold,
+
+                interest_model: state.interest_model,
+                interest_model_address: state.interest_model_address,
+                apr: state.apr,
+                supply_apr: state.supply_apr,
+                apr_ticked: state.apr_ticked, // Interest keeps accruing from the last tick of the exported cluster
+
+                reserves: state.reserves,
+                reserve_factor: state.reserve_factor,
+                flash_loan_fee: state.flash_loan_fee,
+
+                supply_cap: state.supply_cap,
+                borrow_cap: state.borrow_cap,
+
+                status: state.status,
+                operations: state.operations,
+
+                history: AvlTree::new(),
+                history_length: 0,
+                history_start: now(),
+                history_end: now(),
+
+                interest_tick_interval: state.interest_tick_interval,
+                keeper_reward: state.keeper_reward,
             };
 
-            let component = component_state
-                .instantiate()
-                .prepare_to_globalize(cluster_owner)
-                .roles(component_roles)
-                .metadata(component_metadata)
-                .with_address(address_reservation)
-                .globalize();
+            component_state.__assert_state_legal();
 
-            component
+            Self::__globalize(
+                component_state,
+                address_reservation,
+                cluster_owner,
+                cluster_admin_rule,
+                resource_name,
+                resource_symbol,
+            )
         }
 
         //] ------------ Position Operations ----------- /
@@ -224,33 +879,72 @@ mod lattic3_cluster {
         /// # Returns
         /// * A `Bucket` containing the minted supply units corresponding to the supplied amount.
         ///
+        /// On the first supply, `DEAD_UNITS` of the minted units are permanently locked in the cluster.
+        ///
         /// # Panics
         /// * If the provided resource is invalid or if internal state checks fail.
+        /// * If the operation is not allowed by the cluster's status or operation toggles.
+        /// * If the supply would exceed the supply cap.
+        /// * If the first supply is below `MINIMUM_INITIAL_SUPPLY`.
+        /// * If the supply is too small to mint any units.
+        ///
+        /// # Events
+        /// * Emits a `ClusterSupplyEvent` with the supplied amount and minted units.
         pub fn supply(&mut self, supply: Bucket) -> Bucket {
             self.__validate_res_bucket(&supply);
 
             let amount = supply.amount();
             info!("Supplying [{:?} : {:?}]", supply.resource_address(), amount);
 
-            // TODO: Validate that the cluster is ready to accept supply
+            self.__assert_operation_allowed(ClusterOperation::Supply);
+            self.__assert_within_supply_cap(amount);
+
+            // Accrue interest first, so that units are minted at the up-to-date ratio
+            self.__tick_interest();
+
+            // The first supply sets the initial ratio, so it must be large enough and locks away some dead units
+            let dead_units = if self.supply_units == pdec!(0) {
+                assert!(
+                    amount >= MINIMUM_INITIAL_SUPPLY,
+                    "Initial supply must be at least {}",
+                    MINIMUM_INITIAL_SUPPLY
+                );
+                DEAD_UNITS
+            } else {
+                dec!(0)
+            };
 
             self.liquidity.put(supply);
 
             // Mint corresponding number of units
-            let unit_amount = self.get_units(ClusterLayer::Supply, amount);
-            let units = self.supply_unit_manager.mint(unit_amount);
+            let unit_amount = self.get_units(ClusterLayer::Supply, amount, Rounding::Down);
+            assert!(
+                unit_amount > dead_units,
+                "Supply of {} is too small to mint any units",
+                amount
+            );
+
+            let mut units = self.__mint_units(&self.supply_unit_manager, unit_amount);
+            if dead_units > dec!(0) {
+                self.dead_units.put(units.take(dead_units));
+            }
 
             // Update internal state
             self.supply = self.supply.checked_add(amount).unwrap();
             self.supply_units = self.supply_units.checked_add(unit_amount).unwrap();
             self.virtual_supply = self.virtual_supply.checked_add(amount).unwrap();
 
-            // TODO: Verify internal state is legal
-            assert!(self.supply >= pdec!(0), "Negative supply");
-            assert!(self.supply_units >= pdec!(0), "Negative supply units");
-            assert!(self.virtual_supply >= pdec!(0), "Negative virtual supply");
+            // New liquidity goes to queued withdrawals first
+            self.__fill_withdrawal_queue();
+
+            self.__assert_state_legal();
+            self.__refresh_rates();
 
-            self.tick_interest(true);
+            Runtime::emit_event(ClusterSupplyEvent {
+                amount,
+                units: unit_amount,
+                supply_ratio: self.get_ratio(ClusterLayer::Supply),
+            });
 
             // Return units
             info!("Received units: {}", units.amount());
@@ -267,31 +961,33 @@ mod lattic3_cluster {
         ///
         /// # Panics
         /// * If the provided units are invalid or if internal state checks fail.
+        /// * If the operation is not allowed by the cluster's status or operation toggles.
+        /// * If withdrawals remain queued once the queue is filled, or the cluster's liquidity does not cover the
+        ///   withdrawal; see `request_withdrawal`.
+        ///
+        /// # Events
+        /// * Emits a `ClusterWithdrawEvent` with the withdrawn amount and burnt units.
         pub fn withdraw(&mut self, units: Bucket) -> Bucket {
             self.__validate_unit_bucket(&units);
 
-            let unit_amount = units.amount();
             info!("Withdrawing [{:?} : {:?}]", units.resource_address(), units.amount());
 
-            // TODO: Validate that the cluster is ready to withdraw
-
-            // Burn supply units
-            units.burn();
+            self.__assert_operation_allowed(ClusterOperation::Withdraw);
 
-            let amount = self.get_amount(ClusterLayer::Supply, unit_amount);
-            let withdrawn = self.liquidity.take(amount);
+            // Accrue interest first, so that units are redeemed at the up-to-date ratio
+            self.__tick_interest();
 
-            // Update internal state
-            self.supply = self.supply.checked_sub(amount).unwrap();
-            self.supply_units = self.supply_units.checked_sub(unit_amount).unwrap();
-            self.virtual_supply = self.virtual_supply.checked_sub(amount).unwrap();
+            // Queued withdrawals come first; the queue may have been left unfilled since liquidity last returned
+            self.__fill_withdrawal_queue();
+            assert!(
+                self.queued_units.is_empty(),
+                "Withdrawals are queued; request a withdrawal instead"
+            );
 
-            // TODO: Verify internal state is legal
-            assert!(self.supply >= pdec!(0), "Negative supply");
-            assert!(self.supply_units >= pdec!(0), "Negative supply units");
-            assert!(self.virtual_supply >= pdec!(0), "Negative virtual supply");
+            let withdrawn = self.__redeem_units(units);
 
-            self.tick_interest(true);
+            self.__assert_state_legal();
+            self.__refresh_rates();
 
             // Return resource
             info!(
@@ -309,50 +1005,70 @@ mod lattic3_cluster {
         ///
         /// # Returns
         /// * A `Bucket` with the borrowed resource
-        /// * A `Decimal` representing the corresponding 'debt units' (not actually minted).
+        /// * A `Bucket` with the minted debt units, which can only be withdrawn by the cluster admin once deposited.
         ///
         /// # Panics
         /// * If the provided amount is invalid or if internal state checks fail.
-        pub fn borrow(&mut self, amount: Decimal) -> (Bucket, Decimal) {
+        /// * If the operation is not allowed by the cluster's status or operation toggles.
+        /// * If the borrow would exceed the borrow cap.
+        ///
+        /// # Events
+        /// * Emits a `ClusterBorrowEvent` with the borrowed amount and minted debt units.
+        pub fn borrow(&mut self, amount: Decimal) -> (Bucket, Bucket) {
             assert!(amount > dec!(0), "Borrowed amount must be greater than zero");
 
-            let unit_amount = self.get_units(ClusterLayer::Debt, amount);
+            self.__assert_operation_allowed(ClusterOperation::Borrow);
+            self.__assert_within_borrow_cap(amount);
 
-            // TODO: Validate that the cluster is ready to accept borrow
+            // Accrue interest first, so that debt units are minted at the up-to-date ratio
+            self.__tick_interest();
 
-            self.tick_interest(true);
+            let unit_amount = self.get_units(ClusterLayer::Debt, amount, Rounding::Up);
 
             let borrowed = self.liquidity.take(amount);
+            let units = self.__mint_units(&self.debt_unit_manager, unit_amount);
 
             // Update internal state
             self.debt = self.debt.checked_add(amount).unwrap();
             self.debt_units = self.debt_units.checked_add(unit_amount).unwrap();
             self.virtual_debt = self.virtual_debt.checked_add(amount).unwrap();
 
-            // TODO: Verify internal state is legal
-            assert!(self.debt >= pdec!(0), "Negative debt");
-            assert!(self.debt_units >= pdec!(0), "Negative debt units");
-            assert!(self.virtual_debt >= pdec!(0), "Negative virtual debt");
+            self.__assert_state_legal();
+            self.__refresh_rates();
+
+            Runtime::emit_event(ClusterBorrowEvent {
+                amount,
+                debt_units: unit_amount,
+                fixed_rate_loan: None,
+                rate: self.apr,
+            });
 
             // Return resource
             info!("Borrowed [{:?} : {:?}]", borrowed.resource_address(), borrowed.amount());
-            info!("Debt units: {}", unit_amount);
+            info!("Debt units: {}", units.amount());
 
-            (borrowed, unit_amount)
+            (borrowed, units)
         }
 
         /// Repays the given amount of resource to the cluster and burns corresponding debt units.
         ///
         /// # Parameters
         /// * `repayment` - A `Bucket` containing the repayment resource.
+        /// * `units` - A `Bucket` containing the debt units being repaid; units not covered by the repayment are returned.
         ///
         /// # Returns
-        /// * A `Decimal` representing the repaid debt units (not actually burned).
+        /// * A `Bucket` containing the remaining, unrepaid debt units.
         ///
         /// # Panics
-        /// * If the provided repayment is invalid or if internal state checks fail.
-        pub fn repay(&mut self, repayment: Bucket) -> Decimal {
+        /// * If the provided repayment or debt units are invalid or if internal state checks fail.
+        /// * If the repayment covers more debt units than provided.
+        /// * If the operation is not allowed by the cluster's status or operation toggles.
+        ///
+        /// # Events
+        /// * Emits a `ClusterRepayEvent` with the repaid amount and burnt debt units.
+        pub fn repay(&mut self, repayment: Bucket, mut units: Bucket) -> Bucket {
             self.__validate_res_bucket(&repayment);
+            self.__validate_debt_unit_bucket(&units);
 
             let amount = repayment.amount().into();
             info!(
@@ -361,11 +1077,21 @@ mod lattic3_cluster {
                 repayment.amount()
             );
 
-            let unit_amount = self.get_units(ClusterLayer::Debt, amount);
+            self.__assert_operation_allowed(ClusterOperation::Repay);
 
-            // TODO: Validate that the cluster is ready to accept repayment
+            // Accrue interest first, so that debt units are burnt at the up-to-date ratio
+            self.__tick_interest();
+
+            let unit_amount = self.get_units(ClusterLayer::Debt, amount, Rounding::Down);
+            assert!(
+                unit_amount <= units.amount(),
+                "Repayment covers {} debt units, but only {} were provided",
+                unit_amount,
+                units.amount()
+            );
 
             self.liquidity.put(repayment);
+            self.__burn_units(units.take(unit_amount));
 
             // If repayment puts debt into negative, transfer it to supply
             self.debt = if PreciseDecimal::from(amount) > self.debt {
@@ -385,225 +1111,1985 @@ mod lattic3_cluster {
                 self.virtual_debt.checked_sub(amount).unwrap()
             };
 
-            // TODO: Verify internal state is legal
-            assert!(self.debt >= pdec!(0), "Negative debt");
-            assert!(self.debt_units >= pdec!(0), "Negative debt units");
-            assert!(self.virtual_debt >= pdec!(0), "Negative virtual debt");
+            // Repaid liquidity goes to queued withdrawals first
+            self.__fill_withdrawal_queue();
 
-            self.tick_interest(true);
+            self.__assert_state_legal();
+            self.__refresh_rates();
 
-            // Return repaid pool units
-            unit_amount
-        }
+            Runtime::emit_event(ClusterRepayEvent { amount, debt_units: unit_amount, fixed_rate_loan: None });
 
-        //] ------------ Cluster Management ------------ /
+            // Return unrepaid debt units
+            info!("Repaid debt units: {}", unit_amount);
+            units
+        }
 
-        /// Returns the current ratio of supply or debt units to virtual supply or debt.
+        /// Writes off debt which can no longer be recovered, e.g. from a position whose collateral is worth less than
+        /// its debt.
         ///
-        /// If the virtual supply or debt is zero, returns 1.
+        /// The written-off debt is first covered by the accrued reserves; any remainder is socialized across lenders by
+        /// lowering the value of each supply unit.
         ///
         /// # Parameters
-        /// * `layer` - The `ClusterLayer` to get the ratio for.
+        /// * `units` - A `Bucket` containing the debt units to write off.
         ///
-        /// # Returns
-        /// * The supply or debt ratio.
-        pub fn get_ratio(&self, layer: ClusterLayer) -> PreciseDecimal {
-            match layer {
-                ClusterLayer::Supply => {
-                    if self.virtual_supply == pdec!(0) {
-                        pdec!(1)
-                    } else {
-                        self.supply_units.checked_div(self.virtual_supply).unwrap()
-                    }
-                }
-                ClusterLayer::Debt => {
-                    if self.virtual_debt == pdec!(0) {
-                        pdec!(1)
-                    } else {
-                        self.debt_units.checked_div(self.virtual_debt).unwrap()
-                    }
-                }
-            }
-        }
+        /// # Panics
+        /// * If the provided debt units are invalid or if internal state checks fail.
+        /// * If the socialized loss would wipe out the entire supply.
+        ///
+        /// # Events
+        /// * Emits a `BadDebtAbsorbedEvent` with the written-off debt and how the loss was distributed.
+        pub fn absorb_bad_debt(&mut self, units: Bucket) {
+            self.__validate_debt_unit_bucket(&units);
 
-        /// Convert an amount of the resource to its corresponding amount of supply/debt units.
+            self.__tick_interest();
+
+            let unit_amount = units.amount();
+            let share = PreciseDecimal::from(unit_amount).checked_div(self.debt_units).unwrap();
+
+            // Raw and virtual debt backing the written-off units
+            let principal = self.debt.checked_mul(share).unwrap();
+            let written_off = self.virtual_debt.checked_mul(share).unwrap();
+
+            info!("Absorbing bad debt of {} ({} debt units)", written_off, unit_amount);
+
+            self.__burn_units(units);
+
+            // Remove the debt
+            self.debt = self.debt.checked_sub(principal).unwrap();
+            self.debt_units = self.debt_units.checked_sub(unit_amount).unwrap();
+            // If debt units are 0, then all debt is gone and virtual debt should be 0
+            self.virtual_debt = if self.debt_units == pdec!(0) {
+                pdec!(0)
+            } else {
+                self.virtual_debt.checked_sub(written_off).unwrap()
+            };
+
+            let (covered_by_reserves, socialized) = self.__absorb_loss(principal, written_off);
+
+            self.__assert_state_legal();
+            self.__refresh_rates();
+
+            Runtime::emit_event(BadDebtAbsorbedEvent {
+                debt_units: unit_amount,
+                fixed_rate_loan: None,
+                written_off,
+                covered_by_reserves,
+                socialized,
+                supply_ratio: self.get_ratio(ClusterLayer::Supply),
+            });
+        }
+
+        /// Writes off a fixed-rate loan which can no longer be recovered; see `absorb_bad_debt`.
         ///
         /// # Parameters
-        /// * `layer` - The `ClusterLayer` to operate at (either Supply or Debt).
-        /// * `amount` - The amount to convert.
+        /// * `loan` - A `NonFungibleBucket` with the `FixedRateLoan` to write off, which is burnt.
+        ///
+        /// # Panics
+        /// * If the provided loan is invalid or if internal state checks fail.
+        /// * If the socialized loss would wipe out the entire supply.
+        ///
+        /// # Events
+        /// * Emits a `BadDebtAbsorbedEvent` with the written-off loan and how the loss was distributed.
+        pub fn absorb_bad_fixed_debt(&mut self, loan: NonFungibleBucket) {
+            self.__validate_fixed_loan_bucket(&loan);
+
+            self.__tick_interest();
+
+            let id = loan.non_fungible_local_id();
+            let data = loan.non_fungible::<FixedRateLoan>().data();
+            let written_off = PreciseDecimal::from(self.__fixed_loan_owed(&data));
+
+            // Raw debt backing the loan, in proportion to its share of the fixed-rate debt; the last loan backs all of it
+            let principal = if self.fixed_loans == 1 || data.units >= self.fixed_debt_units {
+                self.fixed_debt
+            } else {
+                self.fixed_debt
+                    .checked_mul(data.units.checked_div(self.fixed_debt_units).unwrap())
+                    .unwrap()
+            };
+
+            info!("Absorbing bad fixed-rate debt of {} (loan {:?})", written_off, id);
+
+            self.authority.authorize_with_amount(dec!(1), || loan.burn());
+            self.fixed_loans -= 1;
+
+            // Remove the debt
+            self.fixed_debt = self.fixed_debt.checked_sub(principal).unwrap();
+            self.__remove_fixed_debt(data.units, written_off, data.rate);
+
+            let (covered_by_reserves, socialized) = self.__absorb_loss(principal, written_off);
+
+            self.__assert_state_legal();
+            self.__refresh_rates();
+
+            Runtime::emit_event(BadDebtAbsorbedEvent {
+                debt_units: dec!(0),
+                fixed_rate_loan: Some(id),
+                written_off,
+                covered_by_reserves,
+                socialized,
+                supply_ratio: self.get_ratio(ClusterLayer::Supply),
+            });
+        }
+
+        //] ------------- Withdrawal Queue ------------- /
+
+        /// Queues a withdrawal of the given supply units, for when the cluster's liquidity cannot cover it.
+        ///
+        /// The units are locked in the cluster and redeemed first-come, first-served as supply and repayments bring in
+        /// liquidity; the request is filled as far as possible right away.
+        ///
+        /// # Parameters
+        /// * `units` - A `Bucket` containing the supply units to withdraw.
         ///
         /// # Returns
-        /// * The resultant units.
+        /// * A `NonFungibleBucket` with the `WithdrawalClaim` for the request.
         ///
         /// # Panics
-        /// * If `amount` is less than zero.
-        pub fn get_units(&self, layer: ClusterLayer, amount: Decimal) -> Decimal {
-            assert!(amount > dec!(0), "Amount must be greater than zero");
+        /// * If the provided units are invalid or if internal state checks fail.
+        /// * If the operation is not allowed by the cluster's status or operation toggles.
+        ///
+        /// # Events
+        /// * Emits a `ClusterWithdrawEvent` for each part of a queued withdrawal that is filled.
+        pub fn request_withdrawal(&mut self, units: Bucket) -> NonFungibleBucket {
+            self.__validate_unit_bucket(&units);
 
-            let ratio = self.get_ratio(layer);
-            let amount = amount.checked_mul(ratio).unwrap();
+            self.__assert_operation_allowed(ClusterOperation::Withdraw);
+
+            self.__tick_interest();
+
+            let data = WithdrawalClaim { units: units.amount(), filled: dec!(0), queue_index: self.queue_tail };
+            let claim = self
+                .authority
+                .authorize_with_amount(dec!(1), || self.claim_manager.mint_ruid_non_fungible(data))
+                .as_non_fungible();
+
+            info!(
+                "Queued withdrawal of {} units as claim {:?}",
+                units.amount(),
+                claim.non_fungible_local_id()
+            );
 
-            trunc(amount)
+            self.queued_units.put(units);
+            self.withdrawal_queue
+                .insert(self.queue_tail, claim.non_fungible_local_id());
+            self.queue_tail += 1;
+            self.withdrawal_claims += 1;
+
+            self.__fill_withdrawal_queue();
+
+            self.__assert_state_legal();
+            self.__refresh_rates();
+
+            claim
         }
 
-        /// Converts the amount of units to the corresponding resource amount.
+        /// Redeems the filled part of a withdrawal claim.
         ///
         /// # Parameters
-        /// * `layer` - The `ClusterLayer` to operate at (either Supply or Debt).
-        /// * `unit_amount` - The amount of units to convert.
+        /// * `claim` - A `NonFungibleBucket` with the `WithdrawalClaim`.
         ///
         /// # Returns
-        /// * The converted resource amount.
+        /// * A `Bucket` containing the resource filled so far.
+        /// * The `NonFungibleBucket` with the claim if it is still queued, or `None` if it was fully filled and burnt.
         ///
         /// # Panics
-        /// * If `unit_amount` is less than zero.
-        pub fn get_amount(&self, layer: ClusterLayer, unit_amount: Decimal) -> Decimal {
-            assert!(unit_amount > dec!(0), "Unit amount must be greater than zero");
+        /// * If the provided claim is invalid.
+        pub fn claim_withdrawal(&mut self, claim: NonFungibleBucket) -> (Bucket, Option<NonFungibleBucket>) {
+            self.__validate_claim_bucket(&claim);
 
-            let ratio = self.get_ratio(layer);
-            let amount = unit_amount.checked_div(ratio).unwrap();
+            let id = claim.non_fungible_local_id();
+            let data = claim.non_fungible::<WithdrawalClaim>().data();
+            let withdrawn = self.claimable.take(data.filled);
+
+            info!("Claimed {} from withdrawal claim {:?}", data.filled, id);
+
+            let claim = if data.units == dec!(0) {
+                self.authority.authorize_with_amount(dec!(1), || claim.burn());
+                self.withdrawal_claims -= 1;
+                None
+            } else {
+                self.authority.authorize_with_amount(dec!(1), || {
+                    self.claim_manager.update_non_fungible_data(&id, "filled", dec!(0));
+                });
+                Some(claim)
+            };
 
-            trunc(amount)
+            (withdrawn, claim)
         }
 
-        /// Returns a snapshot of the cluster's state.
+        /// Cancels a withdrawal claim, leaving the queue.
+        ///
+        /// # Parameters
+        /// * `claim` - A `NonFungibleBucket` with the `WithdrawalClaim`.
         ///
         /// # Returns
-        /// * A `ClusterState` containing the current state of the cluster.
-        pub fn get_cluster_state(&self) -> ClusterState {
-            let state = ClusterState {
-                at: now(),
+        /// * A `Bucket` containing the supply units which were not yet redeemed.
+        /// * A `Bucket` containing the resource filled so far.
+        ///
+        /// # Panics
+        /// * If the provided claim is invalid.
+        pub fn cancel_withdrawal(&mut self, claim: NonFungibleBucket) -> (Bucket, Bucket) {
+            self.__validate_claim_bucket(&claim);
 
-                resource: self.resource,
-                supply_unit: self.supply_unit_manager.address(),
-                liquidity: self.liquidity.amount(),
+            let id = claim.non_fungible_local_id();
+            let data = claim.non_fungible::<WithdrawalClaim>().data();
 
-                supply: self.supply,
-                supply_units: self.supply_units,
-                virtual_supply: self.virtual_supply,
-                supply_ratio: self.get_ratio(ClusterLayer::Supply),
+            info!("Cancelled withdrawal claim {:?} with {} units queued", id, data.units);
 
-                debt: self.debt,
-                debt_units: self.debt_units,
-                virtual_debt: self.virtual_debt,
-                debt_ratio: self.get_ratio(ClusterLayer::Debt),
+            self.withdrawal_queue.remove(&data.queue_index);
+            self.authority.authorize_with_amount(dec!(1), || claim.burn());
+            self.withdrawal_claims -= 1;
 
-                apr: self.apr,
-                apr_ticked: self.apr_ticked,
+            (self.queued_units.take(data.units), self.claimable.take(data.filled))
+        }
+
+        /// Fills queued withdrawals from the cluster's liquidity, oldest first.
+        ///
+        /// Supply, repayments and provided liquidity fill the queue automatically; this allows anyone to fill it after
+        /// e.g. a change in status.
+        pub fn fill_withdrawal_queue(&mut self) {
+            self.__tick_interest();
+
+            self.__fill_withdrawal_queue();
+
+            self.__assert_state_legal();
+            self.__refresh_rates();
+        }
+
+        //] -------------- Fixed-Rate Loans ------------ /
+
+        /// Borrows the given amount of resource at a fixed rate, locked at the current offer (see `get_fixed_rate`).
+        ///
+        /// If an existing loan is provided, the amount is added to it instead of opening a new loan; interest accrued on
+        /// it so far is capitalized, and its rate becomes the average of its locked rate and the current offer, weighted
+        /// by the amounts owed.
+        ///
+        /// # Parameters
+        /// * `amount` - The amount of resource to borrow.
+        /// * `loan` - An optional `NonFungibleBucket` with a `FixedRateLoan` to add the amount to.
+        ///
+        /// # Returns
+        /// * A `Bucket` with the borrowed resource.
+        /// * A `NonFungibleBucket` with the `FixedRateLoan`, which can only be withdrawn by the cluster admin once
+        ///   deposited.
+        ///
+        /// # Panics
+        /// * If the provided amount or loan is invalid or if internal state checks fail.
+        /// * If the operation is not allowed by the cluster's status or operation toggles.
+        /// * If the borrow would exceed the borrow cap.
+        ///
+        /// # Events
+        /// * Emits a `ClusterBorrowEvent` with the borrowed amount, the loan and its offered rate.
+        pub fn borrow_fixed(
+            &mut self,
+            amount: Decimal,
+            loan: Option<NonFungibleBucket>,
+        ) -> (Bucket, NonFungibleBucket) {
+            assert!(amount > dec!(0), "Borrowed amount must be greater than zero");
+
+            self.__assert_operation_allowed(ClusterOperation::Borrow);
+            self.__assert_within_borrow_cap(amount);
+
+            // Accrue interest first, so that the rate is offered for the up-to-date utilization
+            self.__tick_interest();
+
+            let rate = self.get_fixed_rate();
+            let borrowed = self.liquidity.take(amount);
+
+            let loan = match loan {
+                Some(loan) => {
+                    self.__validate_fixed_loan_bucket(&loan);
+
+                    let id = loan.non_fungible_local_id();
+                    let data = loan.non_fungible::<FixedRateLoan>().data();
+                    let owed = self.__fixed_loan_owed(&data);
+
+                    // Blend the locked rate with the offer, weighted by the amounts owed
+                    let principal = owed.checked_add(amount).unwrap();
+                    let blended = PreciseDecimal::from(owed)
+                        .checked_mul(data.rate)
+                        .unwrap()
+                        .checked_add(PreciseDecimal::from(amount).checked_mul(rate).unwrap())
+                        .unwrap()
+                        .checked_div(principal)
+                        .unwrap();
+
+                    self.__remove_fixed_debt(data.units, owed.into(), data.rate);
+                    let units = self.__add_fixed_debt(principal.into(), blended);
+                    self.__update_fixed_loan(&id, principal, blended, units);
+                    loan
+                }
+                None => {
+                    let units = self.__add_fixed_debt(amount.into(), rate);
+                    let data = FixedRateLoan { principal: amount, rate, accrued_at: now(), units };
+                    self.fixed_loans += 1;
+
+                    self.authority
+                        .authorize_with_amount(dec!(1), || self.fixed_loan_manager.mint_ruid_non_fungible(data))
+                        .as_non_fungible()
+                }
             };
 
-            info!("Cluster state: {:#?}", state);
-            state
+            // Update internal state
+            self.fixed_debt = self.fixed_debt.checked_add(amount).unwrap();
+
+            self.__assert_state_legal();
+            self.__refresh_rates();
+
+            Runtime::emit_event(ClusterBorrowEvent {
+                amount,
+                debt_units: dec!(0),
+                fixed_rate_loan: Some(loan.non_fungible_local_id()),
+                rate,
+            });
+
+            // Return resource
+            info!(
+                "Borrowed [{:?} : {:?}] at a fixed rate of {}",
+                borrowed.resource_address(),
+                amount,
+                rate
+            );
+
+            (borrowed, loan)
         }
 
-        /// Provides liquidity to the cluster without any increases to supply.
+        /// Repays a fixed-rate loan; repayment above the amount owed is returned.
         ///
         /// # Parameters
-        /// * `provided` - A `Bucket` containing the resource to add to the cluster's liquidity.
+        /// * `repayment` - A `Bucket` containing the repayment resource.
+        /// * `loan` - A `NonFungibleBucket` with the `FixedRateLoan` being repaid.
         ///
+        /// # Returns
+        /// * A `Bucket` containing any excess repayment.
+        /// * The `NonFungibleBucket` with the loan if it is not fully repaid, or `None` if it was repaid and burnt.
         ///
         /// # Panics
-        /// * If the provided resource is invalid or does not match the cluster's resource type.
-        pub fn provide_liquidity(&mut self, provided: Bucket) {
-            self.__validate_res_bucket(&provided);
+        /// * If the provided repayment or loan is invalid or if internal state checks fail.
+        /// * If the operation is not allowed by the cluster's status or operation toggles.
+        ///
+        /// # Events
+        /// * Emits a `ClusterRepayEvent` with the repaid amount and the loan.
+        pub fn repay_fixed(
+            &mut self,
+            mut repayment: Bucket,
+            loan: NonFungibleBucket,
+        ) -> (Bucket, Option<NonFungibleBucket>) {
+            self.__validate_res_bucket(&repayment);
+            self.__validate_fixed_loan_bucket(&loan);
 
-            self.liquidity.put(provided);
+            self.__assert_operation_allowed(ClusterOperation::Repay);
+
+            self.__tick_interest();
+
+            let id = loan.non_fungible_local_id();
+            let data = loan.non_fungible::<FixedRateLoan>().data();
+            let owed = self.__fixed_loan_owed(&data);
+
+            let amount = repayment.amount().min(owed);
+            info!("Repaying {} of {} owed on fixed-rate loan {:?}", amount, owed, id);
+
+            self.liquidity.put(repayment.take(amount));
+
+            // If repayment puts fixed debt into negative, transfer it to supply
+            self.fixed_debt = if PreciseDecimal::from(amount) > self.fixed_debt {
+                self.supply = self
+                    .supply
+                    .checked_add(PreciseDecimal::from(amount).checked_sub(self.fixed_debt).unwrap())
+                    .unwrap();
+                pdec!(0)
+            } else {
+                self.fixed_debt.checked_sub(amount).unwrap()
+            };
+            self.__remove_fixed_debt(data.units, owed.into(), data.rate);
+
+            let remaining = owed.checked_sub(amount).unwrap();
+            let loan = if remaining == dec!(0) {
+                self.authority.authorize_with_amount(dec!(1), || loan.burn());
+                self.fixed_loans -= 1;
+
+                None
+            } else {
+                let units = self.__add_fixed_debt(remaining.into(), data.rate);
+                self.__update_fixed_loan(&id, remaining, data.rate, units);
+                Some(loan)
+            };
+
+            // Repaid liquidity goes to queued withdrawals first
+            self.__fill_withdrawal_queue();
+
+            self.__assert_state_legal();
+            self.__refresh_rates();
+
+            Runtime::emit_event(ClusterRepayEvent { amount, debt_units: dec!(0), fixed_rate_loan: Some(id) });
+
+            (repayment, loan)
         }
 
-        /// Withdraws liquidity from the cluster.
+        /// Resets the rate of a fixed-rate loan to the current offer, once the two have diverged by more than the
+        /// rebalance threshold. Interest accrued so far is capitalized at the previous rate.
         ///
         /// # Parameters
-        /// * `amount` - The amount of liquidity to withdraw.
+        /// * `loan_id` - The `NonFungibleLocalId` of the `FixedRateLoan` to rebalance.
         ///
         /// # Panics
-        /// * If the provided amount is invalid.
-        /// * If the withdrawn amount is more than in the cluster's liquidity reserves.
-        pub fn withdraw_liquidity(&mut self, amount: Decimal) -> Bucket {
-            assert!(amount > dec!(0), "Amount must be greater than zero");
+        /// * If the loan does not exist.
+        /// * If the loan's rate is within the rebalance threshold of the current offer.
+        pub fn rebalance_fixed(&mut self, loan_id: NonFungibleLocalId) {
+            self.__tick_interest();
+
+            let data = self.fixed_loan_manager.get_non_fungible_data::<FixedRateLoan>(&loan_id);
+            let rate = self.get_fixed_rate();
+
+            let divergence = data.rate.checked_sub(rate).unwrap().checked_abs().unwrap();
             assert!(
-                amount <= self.liquidity.amount(),
-                "Trying to withdraw more than liquidity"
+                divergence > self.fixed_rebalance_threshold,
+                "Fixed rate of {} is within {} of the current offer of {}",
+                data.rate,
+                self.fixed_rebalance_threshold,
+                rate
             );
 
-            self.liquidity.take(amount)
+            let owed = self.__fixed_loan_owed(&data);
+
+            self.__remove_fixed_debt(data.units, owed.into(), data.rate);
+            let units = self.__add_fixed_debt(owed.into(), rate);
+            self.__update_fixed_loan(&loan_id, owed, rate, units);
+
+            self.__assert_state_legal();
+
+            info!(
+                "Rebalanced fixed-rate loan {:?} from {} to {}",
+                loan_id, data.rate, rate
+            );
         }
 
-        //] --------- Internal State Management -------- /
+        //] ---------------- Flash Loans --------------- /
 
-        /// Ticks interest on the cluster.
+        /// Lends out liquidity for the duration of the transaction.
+        ///
+        /// The returned receipt cannot be deposited, so the transaction can only succeed if the loan is repaid through
+        /// `flash_repay` within it.
         ///
         /// # Parameters
-        /// * `force` - If true, the interest will be ticked regardless of the time elapsed since the last tick.
-        pub fn tick_interest(&mut self, force: bool) {
-            let interval = now() - self.apr_ticked;
-
-            info!(
-                "Called tick_interest at {}. Last ticked at {} so interval is {}. Interest tick interval is {}.",
-                now(),
-                self.apr_ticked,
-                interval,
-                self.interest_tick_interval
+        /// * `amount` - The amount of resource to borrow.
+        ///
+        /// # Returns
+        /// * A `Bucket` with the borrowed resource.
+        /// * A `NonFungibleBucket` with the `FlashLoanReceipt` of the loan.
+        ///
+        /// # Panics
+        /// * If the amount is not greater than zero, or exceeds the cluster's liquidity.
+        /// * If borrowing is not allowed by the cluster's status or operation toggles.
+        pub fn flash_borrow(&mut self, amount: Decimal) -> (Bucket, NonFungibleBucket) {
+            assert!(amount > dec!(0), "Borrowed amount must be greater than zero");
+            assert!(
+                amount <= self.liquidity.amount(),
+                "Trying to flash borrow more than liquidity"
             );
 
-            if (interval > self.interest_tick_interval) || force {
-                info!("Ticking interest");
-                self.__tick_interest();
-                self.apr_ticked = now();
-            }
-        }
+            self.__assert_operation_allowed(ClusterOperation::Borrow);
 
-        pub fn set_interest_tick_interval(&mut self, interval: i64) {
-            assert!(interval > 0, "Interest tick interval must be greater than zero");
-            self.interest_tick_interval = interval;
-        }
+            self.__tick_interest();
 
-        //] -------------- Private Methods ------------- /
-        fn __validate_res_bucket(&self, bucket: &Bucket) {
-            assert!(bucket.resource_address() == self.resource, "Invalid resource provided");
-            assert!(bucket.amount() > dec!(0), "Provided amount must be greater than zero");
+            // Round the fee up, so that it is never undercharged
+            let fee = round_up(PreciseDecimal::from(amount).checked_mul(self.flash_loan_fee).unwrap());
+            let receipt = self
+                .flash_loan_manager
+                .mint_ruid_non_fungible(FlashLoanReceipt { amount, fee })
+                .as_non_fungible();
+
+            self.flash_loaned = self.flash_loaned.checked_add(amount).unwrap();
+            let borrowed = self.liquidity.take(amount);
+
+            info!("Flash borrowed {} with a fee of {}", amount, fee);
+            (borrowed, receipt)
         }
 
-        fn __validate_unit_bucket(&self, bucket: &Bucket) {
+        /// Repays a flash loan, along with its fee.
+        ///
+        /// The fee is split between suppliers and reserves according to the reserve factor.
+        ///
+        /// # Parameters
+        /// * `repayment` - A `Bucket` containing at least the borrowed amount plus the fee.
+        /// * `receipt` - A `NonFungibleBucket` with the `FlashLoanReceipt` of the loan.
+        ///
+        /// # Returns
+        /// * A `Bucket` containing any excess repayment.
+        ///
+        /// # Panics
+        /// * If the repayment resource or the receipt is invalid.
+        /// * If the repayment does not cover the borrowed amount plus the fee.
+        pub fn flash_repay(&mut self, mut repayment: Bucket, receipt: NonFungibleBucket) -> Bucket {
             assert!(
-                bucket.resource_address() == self.supply_unit_manager.address(),
-                "Invalid unit provided"
+                repayment.resource_address() == self.resource,
+                "Invalid resource provided"
+            );
+            assert!(
+                receipt.resource_address() == self.flash_loan_manager.address(),
+                "Invalid flash loan receipt provided"
+            );
+            assert!(
+                receipt.amount() == dec!(1),
+                "Exactly one flash loan receipt must be provided"
             );
-            assert!(bucket.amount() > dec!(0), "Provided amount must be greater than zero");
-        }
 
-        fn __tick_interest(&mut self) {
-            let interval = now() - self.apr_ticked;
-            let delta_time = PreciseDecimal::from(interval) // I / t_y
-                .checked_div(PreciseDecimal::from(YEAR_IN_SECONDS))
-                .unwrap();
+            let loan = receipt.non_fungible::<FlashLoanReceipt>().data();
+            let owed = loan.amount.checked_add(loan.fee).unwrap();
+            assert!(
+                repayment.amount() >= owed,
+                "Flash loan repayment of {} does not cover the {} owed",
+                repayment.amount(),
+                owed
+            );
 
-            info!("Delta time is {}", delta_time);
+            self.__tick_interest();
 
-            let apr_debt: PreciseDecimal = pdec!(0.1); // apr_d (= 10%)
-            let apr_supply: PreciseDecimal = pdec!(0.05); // apr_s (= 5%)
+            self.liquidity.put(repayment.take(owed));
+            self.flash_loaned = self.flash_loaned.checked_sub(loan.amount).unwrap();
+            receipt.burn();
 
-            info!("APR debt is {}, APR supply is {}", apr_debt, apr_supply);
+            // The fee grows the raw supply; suppliers' share raises the value of their units, the rest is reserved
+            let fee = PreciseDecimal::from(loan.fee);
+            let supplier_fee = if self.supply_units == pdec!(0) {
+                pdec!(0)
+            } else {
+                fee.checked_mul(pdec!(1).checked_sub(self.reserve_factor).unwrap())
+                    .unwrap()
+            };
 
-            let virtual_debt_delta = self // change to virtual_debt
-                .virtual_debt
-                .checked_mul(apr_debt)
-                .unwrap()
-                .checked_mul(delta_time)
-                .unwrap();
-            let virtual_supply_delta = self // change to virtual_supply
-                .virtual_supply
-                .checked_mul(apr_supply)
-                .unwrap()
-                .checked_mul(delta_time)
+            self.supply = self.supply.checked_add(fee).unwrap();
+            self.virtual_supply = self.virtual_supply.checked_add(supplier_fee).unwrap();
+            self.reserves = self
+                .reserves
+                .checked_add(fee.checked_sub(supplier_fee).unwrap())
                 .unwrap();
 
-            info!(
-                "Debt increased by {} and supply increased by {}",
-                virtual_debt_delta, virtual_supply_delta
-            );
+            self.__assert_state_legal();
+            self.__refresh_rates();
+
+            info!("Flash loan of {} repaid with a fee of {}", loan.amount, loan.fee);
+            repayment
+        }
+
+        //] ------------ Cluster Management ------------ /
+
+        /// Returns the current ratio of supply or debt units to virtual supply or debt.
+        ///
+        /// If the virtual supply or debt is zero, returns 1.
+        ///
+        /// # Parameters
+        /// * `layer` - The `ClusterLayer` to get the ratio for.
+        ///
+        /// # Returns
+        /// * The supply or debt ratio.
+        pub fn get_ratio(&self, layer: ClusterLayer) -> PreciseDecimal {
+            match layer {
+                ClusterLayer::Supply => {
+                    if self.virtual_supply == pdec!(0) {
+                        pdec!(1)
+                    } else {
+                        self.supply_units.checked_div(self.virtual_supply).unwrap()
+                    }
+                }
+                ClusterLayer::Debt => {
+                    if self.virtual_debt == pdec!(0) {
+                        pdec!(1)
+                    } else {
+                        self.debt_units.checked_div(self.virtual_debt).unwrap()
+                    }
+                }
+            }
+        }
+
+        /// Convert an amount of the resource to its corresponding amount of supply/debt units.
+        ///
+        /// Units minted on supply, and debt units burnt on repayment, are rounded down; supply units burnt for a requested
+        /// amount, and debt units minted on borrow, are rounded up.
+        ///
+        /// # Parameters
+        /// * `layer` - The `ClusterLayer` to operate at (either Supply or Debt).
+        /// * `amount` - The amount to convert.
+        /// * `rounding` - The direction in which to round the units.
+        ///
+        /// # Returns
+        /// * The resultant units.
+        ///
+        /// # Panics
+        /// * If `amount` is less than zero.
+        pub fn get_units(&self, layer: ClusterLayer, amount: Decimal, rounding: Rounding) -> Decimal {
+            assert!(amount > dec!(0), "Amount must be greater than zero");
+
+            let ratio = self.get_ratio(layer);
+            let units = PreciseDecimal::from(amount).checked_mul(ratio).unwrap();
+
+            rounding.apply(units)
+        }
+
+        /// Converts the amount of units to the corresponding resource amount.
+        ///
+        /// Amounts paid out for supply units are rounded down; amounts owed for debt units are rounded up.
+        ///
+        /// # Parameters
+        /// * `layer` - The `ClusterLayer` to operate at (either Supply or Debt).
+        /// * `unit_amount` - The amount of units to convert.
+        /// * `rounding` - The direction in which to round the amount.
+        ///
+        /// # Returns
+        /// * The converted resource amount.
+        ///
+        /// # Panics
+        /// * If `unit_amount` is less than zero.
+        pub fn get_amount(&self, layer: ClusterLayer, unit_amount: Decimal, rounding: Rounding) -> Decimal {
+            assert!(unit_amount > dec!(0), "Unit amount must be greater than zero");
+
+            let ratio = self.get_ratio(layer);
+            let amount = PreciseDecimal::from(unit_amount).checked_div(ratio).unwrap();
+
+            rounding.apply(amount)
+        }
+
+        /// Converts the amount of units to the corresponding resource amount, including the interest accrued since the
+        /// last tick; unlike `get_amount`, the accrued interest is computed without ticking it into the cluster.
+        ///
+        /// # Parameters
+        /// * `layer` - The `ClusterLayer` to operate at (either Supply or Debt).
+        /// * `unit_amount` - The amount of units to convert.
+        /// * `rounding` - The direction in which to round the amount.
+        ///
+        /// # Returns
+        /// * The converted resource amount, as it would be right after an interest tick.
+        ///
+        /// # Panics
+        /// * If `unit_amount` is less than zero.
+        pub fn preview_amount(&self, layer: ClusterLayer, unit_amount: Decimal, rounding: Rounding) -> Decimal {
+            assert!(unit_amount > dec!(0), "Unit amount must be greater than zero");
+
+            let (virtual_supply, virtual_debt) = self.__pending_virtual();
+            let (units, virtual_amount) = match layer {
+                ClusterLayer::Supply => (self.supply_units, virtual_supply),
+                ClusterLayer::Debt => (self.debt_units, virtual_debt),
+            };
+
+            let ratio = if virtual_amount == pdec!(0) {
+                pdec!(1)
+            } else {
+                units.checked_div(virtual_amount).unwrap()
+            };
+            let amount = PreciseDecimal::from(unit_amount).checked_div(ratio).unwrap();
+
+            rounding.apply(amount)
+        }
+
+        /// Returns the amount currently owed on a fixed-rate loan, rounded up.
+        ///
+        /// # Parameters
+        /// * `loan_id` - The `NonFungibleLocalId` of the `FixedRateLoan`.
+        ///
+        /// # Returns
+        /// * The amount owed, including interest accrued since the loan was last updated.
+        ///
+        /// # Panics
+        /// * If the loan does not exist.
+        pub fn get_fixed_debt(&self, loan_id: NonFungibleLocalId) -> Decimal {
+            let data = self.fixed_loan_manager.get_non_fungible_data::<FixedRateLoan>(&loan_id);
+            self.__fixed_loan_owed(&data)
+        }
+
+        /// Returns the rate currently offered on fixed-rate loans: the borrow rate plus the fixed rate premium.
+        ///
+        /// # Returns
+        /// * The fixed borrow APR.
+        pub fn get_fixed_rate(&self) -> PreciseDecimal {
+            self.apr.checked_add(self.fixed_rate_premium).unwrap()
+        }
+
+        /// Returns a snapshot of the cluster's state.
+        ///
+        /// # Returns
+        /// * A `ClusterState` containing the current state of the cluster.
+        pub fn get_cluster_state(&self) -> ClusterState {
+            let state = ClusterState {
+                at: now(),
+
+                resource: self.resource,
+                authority: self.authority.resource_address(),
+                supply_unit: self.supply_unit_manager.address(),
+                debt_unit: self.debt_unit_manager.address(),
+                flash_loan_receipt: self.flash_loan_manager.address(),
+                fixed_rate_loan: self.fixed_loan_manager.address(),
+                withdrawal_claim: self.claim_manager.address(),
+                liquidity: self.liquidity.amount(),
+                flash_loaned: self.flash_loaned,
+                provided_liquidity: self.provided_liquidity,
+
+                queued_units: self.queued_units.amount(),
+                claimable: self.claimable.amount(),
+                withdrawal_claims: self.withdrawal_claims,
+
+                supply: self.supply,
+                supply_units: self.supply_units,
+                virtual_supply: self.virtual_supply,
+                supply_ratio: self.get_ratio(ClusterLayer::Supply),
+
+                debt: self.debt,
+                debt_units: self.debt_units,
+                virtual_debt: self.virtual_debt,
+                debt_ratio: self.get_ratio(ClusterLayer::Debt),
+
+                fixed_debt: self.fixed_debt,
+                fixed_debt_units: self.fixed_debt_units,
+                virtual_fixed_debt: self.virtual_fixed_debt,
+                fixed_rate: self.fixed_rate,
+                fixed_loans: self.fixed_loans,
+                fixed_rate_premium: self.fixed_rate_premium,
+                fixed_rebalance_threshold: self.fixed_rebalance_threshold,
+
+                utilization: utilization(self.supply, self.__total_debt()),
+                apr: self.apr,
+                supply_apr: self.supply_apr,
+                apr_ticked: self.apr_ticked,
+
+                interest_model: self.interest_model.clone(),
+                interest_model_address: self.interest_model_address,
+                interest_tick_interval: self.interest_tick_interval,
+                keeper_reward: self.keeper_reward,
+
+                reserves: self.reserves,
+                reserve_factor: self.reserve_factor,
+                flash_loan_fee: self.flash_loan_fee,
+
+                supply_cap: self.supply_cap,
+                borrow_cap: self.borrow_cap,
+
+                status: self.status.clone(),
+                operations: self.operations.clone(),
+            };
+
+            info!("Cluster state: {:#?}", state);
+            state
+        }
+
+        /// Returns the snapshots recorded within the given time range, oldest first.
+        ///
+        /// At most `HISTORY_PAGE_SIZE` snapshots are returned; to fetch the next page, call again with `from` set to one
+        /// second after the last returned snapshot.
+        ///
+        /// # Parameters
+        /// * `from` - The start of the range (inclusive), in seconds since the Unix epoch.
+        /// * `to` - The end of the range (exclusive), in seconds since the Unix epoch.
+        ///
+        /// # Returns
+        /// * A `Vec<ClusterSnapshot>` of the snapshots recorded within the range.
+        ///
+        /// # Panics
+        /// * If `from` is after `to`.
+        pub fn get_history(&self, from: i64, to: i64) -> Vec<ClusterSnapshot> {
+            assert!(from <= to, "History range start must not be after its end");
+
+            self.history
+                .range(from..to)
+                .take(HISTORY_PAGE_SIZE)
+                .map(|(_, snapshot, _)| snapshot)
+                .collect()
+        }
+
+        /// Checks the cluster's internal invariants.
+        ///
+        /// The following must hold:
+        /// * All tracked amounts, units and reserves are non-negative.
+        /// * The vault balance plus debt reconciles with supply plus provided liquidity.
+        /// * Supply/debt units are zero if and only if the virtual supply/debt is zero.
+        /// * The supply and debt ratios are positive.
+        /// * The tracked supply and debt units match the total supplies of their unit resources.
+        /// * The tracked fixed-rate loans and withdrawal claims match the total supplies of their resources.
+        ///
+        /// # Returns
+        /// * A `ClusterStateReport` listing any violated invariants.
+        pub fn verify_state(&self) -> ClusterStateReport {
+            let report = self.__verify_state();
+
+            info!("Cluster state report: {:#?}", report);
+            report
+        }
+
+        /// Provides liquidity to the cluster without any increases to supply.
+        ///
+        /// # Parameters
+        /// * `provided` - A `Bucket` containing the resource to add to the cluster's liquidity.
+        ///
+        /// # Panics
+        /// * If the provided resource is invalid or does not match the cluster's resource type.
+        ///
+        /// # Events
+        /// * Emits a `LiquidityProvidedEvent` with the provided amount.
+        pub fn provide_liquidity(&mut self, provided: Bucket) {
+            self.__validate_res_bucket(&provided);
+
+            let amount = provided.amount();
+            self.provided_liquidity = self.provided_liquidity.checked_add(amount).unwrap();
+            self.liquidity.put(provided);
+
+            self.__fill_withdrawal_queue();
+
+            self.__assert_state_legal();
+
+            Runtime::emit_event(LiquidityProvidedEvent { amount, provided_liquidity: self.provided_liquidity });
+        }
+
+        /// Withdraws liquidity from the cluster.
+        ///
+        /// Liquidity provided through `provide_liquidity` is withdrawn first; any remainder is taken out of the supply.
+        ///
+        /// # Parameters
+        /// * `amount` - The amount of liquidity to withdraw.
+        ///
+        /// # Panics
+        /// * If the provided amount is invalid.
+        /// * If the withdrawn amount is more than in the cluster's liquidity reserves.
+        ///
+        /// # Events
+        /// * Emits a `LiquidityWithdrawnEvent` with the withdrawn amount.
+        pub fn withdraw_liquidity(&mut self, amount: Decimal) -> Bucket {
+            assert!(amount > dec!(0), "Amount must be greater than zero");
+            assert!(
+                amount <= self.liquidity.amount(),
+                "Trying to withdraw more than liquidity"
+            );
+
+            // Accrue interest at the utilization in effect before the supply changes
+            self.__tick_interest();
+
+            let from_provided = self.provided_liquidity.min(PreciseDecimal::from(amount));
+            let from_supply = PreciseDecimal::from(amount).checked_sub(from_provided).unwrap();
+
+            self.provided_liquidity = self.provided_liquidity.checked_sub(from_provided).unwrap();
+            self.supply = self.supply.checked_sub(from_supply).unwrap();
+
+            let withdrawn = self.liquidity.take(amount);
+
+            self.__assert_state_legal();
+            self.__refresh_rates();
+
+            Runtime::emit_event(LiquidityWithdrawnEvent { amount, provided_liquidity: self.provided_liquidity });
+
+            withdrawn
+        }
+
+        /// Withdraws accrued protocol reserves from the cluster.
+        ///
+        /// Only interest accrued to the protocol can be collected; lenders' supply is never paid out.
+        ///
+        /// # Parameters
+        /// * `amount` - The amount of reserves to collect. If not provided, all collectable reserves are withdrawn.
+        ///
+        /// # Returns
+        /// * A `Bucket` containing the collected reserves.
+        ///
+        /// # Panics
+        /// * If the amount is not greater than zero.
+        /// * If the amount exceeds the accrued reserves, or the liquidity available to pay them out.
+        pub fn collect_reserves(&mut self, amount: Option<Decimal>) -> Bucket {
+            self.__tick_interest();
+
+            let collectable = self.__collectable_reserves();
+            let amount = amount.unwrap_or(collectable);
+
+            assert!(amount > dec!(0), "Amount must be greater than zero");
+            assert!(
+                PreciseDecimal::from(amount) <= self.reserves,
+                "Trying to collect more than the accrued reserves"
+            );
+            assert!(
+                amount <= collectable,
+                "Collecting reserves would pay out supplied funds"
+            );
+
+            // Reserves are held within the raw supply, so both decrease together
+            self.reserves = self.reserves.checked_sub(amount).unwrap();
+            self.supply = self.supply.checked_sub(amount).unwrap();
+
+            let collected = self.liquidity.take(amount);
+
+            self.__assert_state_legal();
+            self.__refresh_rates();
+
+            info!("Collected reserves: {}", amount);
+            collected
+        }
+
+        /// Exports the cluster's state, liquidity and authority badge for migration to another cluster version.
+        ///
+        /// Interest is ticked first, so the exported state is up to date. The cluster is paused and all operations are
+        /// disabled, as it can no longer mint or burn units; pass the outputs to `instantiate_from_state` to take over.
+        ///
+        /// # Returns
+        /// * A `Bucket` containing all of the cluster's liquidity.
+        /// * A `FungibleBucket` containing the cluster's authority badge.
+        /// * The `ClusterState` of the cluster at export.
+        ///
+        /// # Panics
+        /// * If a flash loan or withdrawal claim is outstanding.
+        pub fn export_state(&mut self) -> (Bucket, FungibleBucket, ClusterState) {
+            assert!(
+                self.flash_loaned == dec!(0),
+                "Cannot export a cluster with outstanding flash loans"
+            );
+            assert!(
+                self.withdrawal_claims == 0,
+                "Cannot export a cluster with outstanding withdrawal claims"
+            );
+
+            self.__tick_interest();
+
+            let state = self.get_cluster_state();
+
+            // Retire the cluster
+            self.status = ClusterStatus::Paused;
+            self.operations = ClusterOperations { supply: false, withdraw: false, borrow: false, repay: false };
+
+            let liquidity = self.liquidity.take_all();
+            let authority = self.authority.take_all();
+
+            info!("Exported cluster state: {:#?}", state);
+            (liquidity, authority, state)
+        }
+
+        //] --------- Internal State Management -------- /
+
+        /// Ticks interest on the cluster.
+        ///
+        /// A non-forced tick is only performed once `interest_tick_interval` seconds have elapsed since the last tick,
+        /// and pays its caller up to `keeper_reward` out of the reserves accrued by that tick, so that quiet clusters are
+        /// kept up to date by keepers. Ticks that accrue nothing are not rewarded.
+        ///
+        /// # Parameters
+        /// * `force` - If true, the interest will be ticked regardless of the time elapsed since the last tick. Forced
+        ///   ticks are never rewarded.
+        ///
+        /// # Returns
+        /// * A `Bucket` containing the keeper reward, if a due non-forced tick was performed and a reward was paid.
+        ///
+        /// # Events
+        /// * Emits an `InterestTickEvent` with the accrued interest if interest is ticked.
+        /// * Emits a `KeeperRewardEvent` if a keeper reward is paid.
+        pub fn tick_interest(&mut self, force: bool) -> Option<Bucket> {
+            let interval = now() - self.apr_ticked;
+
+            info!(
+                "Called tick_interest at {}. Last ticked at {} so interval is {}. Interest tick interval is {}.",
+                now(),
+                self.apr_ticked,
+                interval,
+                self.interest_tick_interval
+            );
+
+            if force {
+                info!("Ticking interest (forced)");
+                self.__tick_interest();
+                self.__refresh_rates();
+                return None;
+            }
+
+            if interval <= self.interest_tick_interval {
+                return None;
+            }
+
+            info!("Ticking interest");
+            let reserves_delta = self.__tick_interest();
+
+            // The reward is capped by the reserves the tick accrued, so keepers are never paid for idle ticks or out of
+            // reserves accrued earlier, and by the collectable reserves, so they are never paid out of supplied funds
+            let reward = self
+                .keeper_reward
+                .min(round_down(reserves_delta))
+                .min(self.__collectable_reserves());
+            if reward <= dec!(0) {
+                self.__refresh_rates();
+                return None;
+            }
+
+            // Reserves are held within the raw supply, so both decrease together
+            self.reserves = self.reserves.checked_sub(reward).unwrap();
+            self.supply = self.supply.checked_sub(reward).unwrap();
+
+            let paid = self.liquidity.take(reward);
+
+            self.__assert_state_legal();
+            self.__refresh_rates();
+
+            Runtime::emit_event(KeeperRewardEvent { amount: reward, elapsed: interval });
+
+            info!("Paid keeper reward: {}", reward);
+            Some(paid)
+        }
+
+        /// Sets the minimum time between non-forced interest ticks.
+        ///
+        /// # Parameters
+        /// * `interval` - The interval, in seconds.
+        ///
+        /// # Panics
+        /// * If the interval is not greater than zero.
+        pub fn set_interest_tick_interval(&mut self, interval: i64) {
+            assert!(interval > 0, "Interest tick interval must be greater than zero");
+            self.interest_tick_interval = interval;
+        }
+
+        /// Sets the reward paid out of reserves to the caller of a due, non-forced interest tick.
+        ///
+        /// # Parameters
+        /// * `reward` - The maximum reward per tick; zero disables keeper rewards.
+        ///
+        /// # Panics
+        /// * If the reward is negative.
+        pub fn set_keeper_reward(&mut self, reward: Decimal) {
+            assert!(reward >= dec!(0), "Keeper reward must not be negative");
+            self.keeper_reward = reward;
+        }
+
+        /// Replaces the interest rate curve of the cluster.
+        ///
+        /// Interest is ticked before the swap, so that the time elapsed so far accrues at the previous rates.
+        ///
+        /// # Parameters
+        /// * `model` - The new `InterestRateModel`.
+        ///
+        /// # Panics
+        /// * If the model parameters are invalid (see `InterestRateModel::validate`).
+        pub fn set_interest_model(&mut self, model: InterestRateModel) {
+            model.validate();
+
+            self.__tick_interest();
+            self.interest_model = model;
+
+            // Refresh the reported rates for the current utilization
+            self.__refresh_rates();
+        }
+
+        /// Links an external `InterestModel` component, which is used in place of the built-in curve.
+        ///
+        /// The component must expose `get_rates(supply, debt, liquidity, elapsed) -> (borrow_rate, supply_rate)`.
+        /// Interest is ticked before the swap, so that the time elapsed so far accrues at the previous rates.
+        ///
+        /// # Parameters
+        /// * `interest_model_address` - The `ComponentAddress` of the interest model.
+        ///
+        /// # Panics
+        /// * If the linked model returns invalid rates.
+        pub fn link_interest_model(&mut self, interest_model_address: ComponentAddress) {
+            self.__tick_interest();
+            self.interest_model_address = Some(interest_model_address);
+
+            // Refresh the reported rates; also ensures the model is callable before it is used for accrual
+            self.__refresh_rates();
+        }
+
+        /// Unlinks the external interest model, reverting the cluster to its built-in curve.
+        pub fn unlink_interest_model(&mut self) {
+            self.__tick_interest();
+            self.interest_model_address = None;
+
+            self.__refresh_rates();
+        }
+
+        /// Sets the share of supply interest withheld by the protocol as reserves.
+        ///
+        /// Interest is ticked before the change, so that the time elapsed so far accrues at the previous factor.
+        ///
+        /// # Parameters
+        /// * `reserve_factor` - The new reserve factor.
+        ///
+        /// # Panics
+        /// * If the reserve factor is not within [0, 1).
+        pub fn set_reserve_factor(&mut self, reserve_factor: PreciseDecimal) {
+            assert!(
+                reserve_factor >= pdec!(0) && reserve_factor < pdec!(1),
+                "Reserve factor must be between 0 (inclusive) and 1 (exclusive)"
+            );
+
+            self.__tick_interest();
+            self.reserve_factor = reserve_factor;
+
+            self.__refresh_rates();
+        }
+
+        /// Sets the fee charged on flash loans, as a share of the borrowed amount.
+        ///
+        /// # Parameters
+        /// * `flash_loan_fee` - The new flash loan fee.
+        ///
+        /// # Panics
+        /// * If the fee is not within [0, 1].
+        pub fn set_flash_loan_fee(&mut self, flash_loan_fee: PreciseDecimal) {
+            assert!(
+                flash_loan_fee >= pdec!(0) && flash_loan_fee <= pdec!(1),
+                "Flash loan fee must be between 0 and 1 (inclusive)"
+            );
+
+            self.flash_loan_fee = flash_loan_fee;
+        }
+
+        /// Sets the premium over the borrow rate charged on new fixed-rate loans. Existing loans are unaffected.
+        ///
+        /// # Parameters
+        /// * `fixed_rate_premium` - The new premium.
+        ///
+        /// # Panics
+        /// * If the premium is negative.
+        pub fn set_fixed_rate_premium(&mut self, fixed_rate_premium: PreciseDecimal) {
+            assert!(
+                fixed_rate_premium >= pdec!(0),
+                "Fixed rate premium must be non-negative"
+            );
+
+            self.fixed_rate_premium = fixed_rate_premium;
+        }
+
+        /// Sets the divergence between a fixed-rate loan's rate and the current offer above which it can be rebalanced.
+        ///
+        /// # Parameters
+        /// * `fixed_rebalance_threshold` - The new threshold.
+        ///
+        /// # Panics
+        /// * If the threshold is negative.
+        pub fn set_fixed_rebalance_threshold(&mut self, fixed_rebalance_threshold: PreciseDecimal) {
+            assert!(
+                fixed_rebalance_threshold >= pdec!(0),
+                "Fixed rebalance threshold must be non-negative"
+            );
+
+            self.fixed_rebalance_threshold = fixed_rebalance_threshold;
+        }
+
+        /// Sets the maximum virtual supply of the cluster. Existing supply above the cap is unaffected.
+        ///
+        /// # Parameters
+        /// * `supply_cap` - The new cap, or `None` to remove it.
+        ///
+        /// # Panics
+        /// * If the cap is negative.
+        pub fn set_supply_cap(&mut self, supply_cap: Option<Decimal>) {
+            if let Some(cap) = supply_cap {
+                assert!(cap >= dec!(0), "Supply cap must be non-negative");
+            }
+
+            self.supply_cap = supply_cap;
+        }
+
+        /// Sets the maximum virtual debt of the cluster. Existing debt above the cap is unaffected.
+        ///
+        /// # Parameters
+        /// * `borrow_cap` - The new cap, or `None` to remove it.
+        ///
+        /// # Panics
+        /// * If the cap is negative.
+        pub fn set_borrow_cap(&mut self, borrow_cap: Option<Decimal>) {
+            if let Some(cap) = borrow_cap {
+                assert!(cap >= dec!(0), "Borrow cap must be non-negative");
+            }
+
+            self.borrow_cap = borrow_cap;
+        }
+
+        /// Sets the operational status of the cluster.
+        ///
+        /// # Parameters
+        /// * `status` - The new `ClusterStatus`.
+        pub fn set_status(&mut self, status: ClusterStatus) {
+            info!("Cluster status changed from {:?} to {:?}", self.status, status);
+            self.status = status;
+        }
+
+        /// Enables or disables a single operation, independently of the cluster status.
+        ///
+        /// # Parameters
+        /// * `operation` - The `ClusterOperation` to toggle.
+        /// * `enabled` - Whether the operation is enabled.
+        pub fn set_operation_enabled(&mut self, operation: ClusterOperation, enabled: bool) {
+            match operation {
+                ClusterOperation::Supply => self.operations.supply = enabled,
+                ClusterOperation::Withdraw => self.operations.withdraw = enabled,
+                ClusterOperation::Borrow => self.operations.borrow = enabled,
+                ClusterOperation::Repay => self.operations.repay = enabled,
+            }
+        }
+
+        //] -------------- Private Methods ------------- /
+        /// Returns the name and symbol of the given resource.
+        fn __resource_metadata(resource: ResourceAddress) -> (String, String) {
+            let resource_manager = ResourceManager::from_address(resource);
+
+            let resource_name: String = resource_manager
+                .get_metadata("name")
+                .expect(format!("Couldn't get metadata (name) for {:?}", resource).as_str())
+                .expect(format!("Metadata (name) for {:?} was none", resource).as_str());
+            let resource_symbol: String = resource_manager
+                .get_metadata("symbol")
+                .expect(format!("Couldn't get metadata (symbol) for {:?}", resource).as_str())
+                .expect(format!("Metadata (symbol) for {:?} was none", resource).as_str());
+
+            (resource_name, resource_symbol)
+        }
+
+        /// Creates the flash loan receipt resource; it can never be deposited, forcing repayment within the same
+        /// transaction.
+        fn __create_flash_loan_manager(
+            component_address: ComponentAddress,
+            cluster_owner: &OwnerRole,
+            resource_name: &String,
+        ) -> ResourceManager {
+            let component_access_rule = rule!(require(global_caller(component_address)));
+
+            ResourceBuilder::new_ruid_non_fungible::<FlashLoanReceipt>(cluster_owner.clone())
+                .metadata(metadata! {
+                    roles {
+                        metadata_setter         => OWNER;
+                        metadata_setter_updater => OWNER;
+                        metadata_locker         => OWNER;
+                        metadata_locker_updater => rule!(deny_all);
+                    },
+                    init {
+                        "name" => format!("Lattic3 {} Flash Loan", resource_name), locked;
+                    }
+                })
+                .burn_roles(burn_roles! {
+                    burner         => component_access_rule.clone();
+                    burner_updater => rule!(deny_all);
+                })
+                .mint_roles(mint_roles! {
+                    minter         => component_access_rule;
+                    minter_updater => rule!(deny_all);
+                })
+                .deposit_roles(deposit_roles! {
+                    depositor         => rule!(deny_all);
+                    depositor_updater => rule!(deny_all);
+                })
+                .create_with_no_initial_supply()
+                .into()
+        }
+
+        /// Globalizes the cluster component with its metadata and roles.
+        fn __globalize(
+            component_state: Cluster,
+            address_reservation: GlobalAddressReservation,
+            cluster_owner: OwnerRole,
+            cluster_admin_rule: AccessRule,
+            resource_name: String,
+            resource_symbol: String,
+        ) -> Global<Cluster> {
+            let name = format!("Lattic3 {} Cluster", resource_symbol);
+            let description = format!("Cluster for the Lattic3 lending platform. Holds {}", resource_name);
+
+            let component_metadata = metadata! {
+                roles {
+                    metadata_setter         => OWNER;
+                    metadata_setter_updater => OWNER;
+                    metadata_locker         => OWNER;
+                    metadata_locker_updater => rule!(deny_all);
+                },
+                init {
+                    "name"        => name, locked;
+                    "description" => description, locked;
+                }
+            };
+
+            let component_roles = roles! {
+                admin => cluster_admin_rule;
+            };
+
+            let component = component_state
+                .instantiate()
+                .prepare_to_globalize(cluster_owner)
+                .roles(component_roles)
+                .metadata(component_metadata)
+                .with_address(address_reservation)
+                .globalize();
+
+            component
+        }
+
+        /// Mints units of the given manager with the cluster's authority badge.
+        fn __mint_units(&self, manager: &ResourceManager, amount: Decimal) -> Bucket {
+            self.authority.authorize_with_amount(dec!(1), || manager.mint(amount))
+        }
+
+        /// Burns units with the cluster's authority badge.
+        fn __burn_units(&self, units: Bucket) {
+            self.authority.authorize_with_amount(dec!(1), || units.burn());
+        }
+
+        fn __validate_res_bucket(&self, bucket: &Bucket) {
+            assert!(bucket.resource_address() == self.resource, "Invalid resource provided");
+            assert!(bucket.amount() > dec!(0), "Provided amount must be greater than zero");
+        }
+
+        fn __validate_unit_bucket(&self, bucket: &Bucket) {
+            assert!(
+                bucket.resource_address() == self.supply_unit_manager.address(),
+                "Invalid unit provided"
+            );
+            assert!(bucket.amount() > dec!(0), "Provided amount must be greater than zero");
+        }
+
+        fn __validate_debt_unit_bucket(&self, bucket: &Bucket) {
+            assert!(
+                bucket.resource_address() == self.debt_unit_manager.address(),
+                "Invalid debt unit provided"
+            );
+            assert!(bucket.amount() > dec!(0), "Provided amount must be greater than zero");
+        }
+
+        fn __validate_claim_bucket(&self, bucket: &NonFungibleBucket) {
+            assert!(
+                bucket.resource_address() == self.claim_manager.address(),
+                "Invalid withdrawal claim provided"
+            );
+            assert!(
+                bucket.amount() == dec!(1),
+                "Exactly one withdrawal claim must be provided"
+            );
+        }
+
+        /// Burns the given supply units and takes the resource they are worth out of the liquidity.
+        fn __redeem_units(&mut self, units: Bucket) -> Bucket {
+            let unit_amount = units.amount();
+
+            // Burn supply units
+            self.__burn_units(units);
+
+            let amount = self.get_amount(ClusterLayer::Supply, unit_amount, Rounding::Down);
+            let withdrawn = self.liquidity.take(amount);
+
+            // Update internal state
+            self.supply = self.supply.checked_sub(amount).unwrap();
+            self.supply_units = self.supply_units.checked_sub(unit_amount).unwrap();
+            // If supply units are 0, then all supply withdrawn and virtual supply should be 0
+            self.virtual_supply = if self.supply_units == pdec!(0) {
+                pdec!(0)
+            } else {
+                self.virtual_supply.checked_sub(amount).unwrap()
+            };
+
+            Runtime::emit_event(ClusterWithdrawEvent {
+                amount,
+                units: unit_amount,
+                supply_ratio: self.get_ratio(ClusterLayer::Supply),
+            });
+
+            withdrawn
+        }
+
+        /// Redeems queued supply units from the available liquidity, oldest request first, visiting at most
+        /// `WITHDRAWAL_FILL_LIMIT` requests.
+        fn __fill_withdrawal_queue(&mut self) {
+            if !self.operations.is_allowed(&ClusterOperation::Withdraw, &self.status) {
+                return;
+            }
+
+            for _ in 0..WITHDRAWAL_FILL_LIMIT {
+                if self.queue_head == self.queue_tail || self.liquidity.is_empty() {
+                    break;
+                }
+
+                // Skip over cancelled requests
+                let id = match self.withdrawal_queue.get(&self.queue_head) {
+                    Some(id) => id.clone(),
+                    None => {
+                        self.queue_head += 1;
+                        continue;
+                    }
+                };
+
+                let claim = self.claim_manager.get_non_fungible_data::<WithdrawalClaim>(&id);
+
+                // Redeem as many of the claim's units as the liquidity covers
+                let covered = self.get_units(ClusterLayer::Supply, self.liquidity.amount(), Rounding::Down);
+                let unit_amount = claim.units.min(covered);
+                if unit_amount == dec!(0) {
+                    break;
+                }
+
+                let units = self.queued_units.take(unit_amount);
+                let withdrawn = self.__redeem_units(units);
+
+                let remaining = claim.units.checked_sub(unit_amount).unwrap();
+                let filled = claim.filled.checked_add(withdrawn.amount()).unwrap();
+                self.claimable.put(withdrawn);
+
+                self.authority.authorize_with_amount(dec!(1), || {
+                    self.claim_manager.update_non_fungible_data(&id, "units", remaining);
+                    self.claim_manager.update_non_fungible_data(&id, "filled", filled);
+                });
+
+                info!(
+                    "Filled {} of withdrawal claim {:?}; {} units remain queued",
+                    filled, id, remaining
+                );
+
+                if remaining > dec!(0) {
+                    break;
+                }
+
+                self.withdrawal_queue.remove(&self.queue_head);
+                self.queue_head += 1;
+            }
+        }
+
+        fn __validate_fixed_loan_bucket(&self, bucket: &NonFungibleBucket) {
+            assert!(
+                bucket.resource_address() == self.fixed_loan_manager.address(),
+                "Invalid fixed-rate loan provided"
+            );
+            assert!(
+                bucket.amount() == dec!(1),
+                "Exactly one fixed-rate loan must be provided"
+            );
+        }
+
+        /// Takes the principal of written-off debt out of the supply and provided liquidity, covering the written-off
+        /// value with reserves and socializing the rest; returns the amounts covered by reserves and socialized.
+        fn __absorb_loss(
+            &mut self,
+            principal: PreciseDecimal,
+            written_off: PreciseDecimal,
+        ) -> (PreciseDecimal, PreciseDecimal) {
+            // The lent-out principal is lost; take it out of the supply first, then out of the provided liquidity
+            let from_supply = self.supply.min(principal);
+            let from_provided = self.provided_liquidity.min(principal.checked_sub(from_supply).unwrap());
+            let uncovered = principal
+                .checked_sub(from_supply)
+                .unwrap()
+                .checked_sub(from_provided)
+                .unwrap();
+            self.supply = self.supply.checked_sub(from_supply).unwrap();
+            self.provided_liquidity = self.provided_liquidity.checked_sub(from_provided).unwrap();
+
+            // Cover the loss with reserves, then spread the rest over lenders, along with any principal which neither
+            // the supply nor the provided liquidity could cover
+            let covered_by_reserves = self.reserves.min(written_off);
+            let socialized = written_off
+                .checked_sub(covered_by_reserves)
+                .unwrap()
+                .checked_add(uncovered)
+                .unwrap();
+
+            assert!(
+                self.supply_units == pdec!(0) || socialized < self.virtual_supply,
+                "Bad debt of {} exceeds the cluster's supply",
+                socialized
+            );
+
+            self.reserves = self.reserves.checked_sub(covered_by_reserves).unwrap();
+            self.virtual_supply = self.virtual_supply.checked_sub(socialized).unwrap().max(pdec!(0));
+
+            (covered_by_reserves, socialized)
+        }
+
+        /// Returns the total raw debt, variable and fixed-rate.
+        fn __total_debt(&self) -> PreciseDecimal {
+            self.debt.checked_add(self.fixed_debt).unwrap()
+        }
+
+        /// Returns the amount owed on a fixed-rate loan at its locked rate, rounded up.
+        fn __fixed_loan_owed(&self, loan: &FixedRateLoan) -> Decimal {
+            let delta_time = PreciseDecimal::from(now() - loan.accrued_at)
+                .checked_div(PreciseDecimal::from(YEAR_IN_SECONDS))
+                .unwrap();
+
+            round_up(
+                PreciseDecimal::from(loan.principal)
+                    .checked_mul(compound(loan.rate, delta_time))
+                    .unwrap(),
+            )
+        }
+
+        /// Capitalizes a fixed-rate loan at the given principal, rate and share of the fixed-rate debt, as of now.
+        fn __update_fixed_loan(
+            &self,
+            id: &NonFungibleLocalId,
+            principal: Decimal,
+            rate: PreciseDecimal,
+            units: PreciseDecimal,
+        ) {
+            self.authority.authorize_with_amount(dec!(1), || {
+                self.fixed_loan_manager
+                    .update_non_fungible_data(id, "principal", principal);
+                self.fixed_loan_manager.update_non_fungible_data(id, "rate", rate);
+                self.fixed_loan_manager
+                    .update_non_fungible_data(id, "accrued_at", now());
+                self.fixed_loan_manager.update_non_fungible_data(id, "units", units);
+            });
+        }
+
+        /// Adds a fixed-rate loan owing `amount` at the given rate to the aggregate, updating the weighted average rate.
+        ///
+        /// Returns the loan's units: its share of the virtual fixed-rate debt, which grows with the aggregate.
+        fn __add_fixed_debt(&mut self, amount: PreciseDecimal, rate: PreciseDecimal) -> PreciseDecimal {
+            let units = if self.fixed_debt_units == pdec!(0) || self.virtual_fixed_debt == pdec!(0) {
+                amount
+            } else {
+                amount
+                    .checked_mul(self.fixed_debt_units)
+                    .unwrap()
+                    .checked_div(self.virtual_fixed_debt)
+                    .unwrap()
+            };
+            let virtual_fixed_debt = self.virtual_fixed_debt.checked_add(amount).unwrap();
+
+            self.fixed_rate = self
+                .fixed_rate
+                .checked_mul(self.virtual_fixed_debt)
+                .unwrap()
+                .checked_add(rate.checked_mul(amount).unwrap())
+                .unwrap()
+                .checked_div(virtual_fixed_debt)
+                .unwrap();
+            self.virtual_fixed_debt = virtual_fixed_debt;
+            self.fixed_debt_units = self.fixed_debt_units.checked_add(units).unwrap();
+
+            units
+        }
+
+        /// Removes a fixed-rate loan owing `owed` at the given rate from the aggregate, updating the weighted average rate.
+        ///
+        /// The aggregate compounds at the average rate, so the loan's share of it differs from what the loan owes at its
+        /// own rate; the difference is settled as interest, so suppliers and reserves are credited what the loan paid.
+        fn __remove_fixed_debt(&mut self, units: PreciseDecimal, owed: PreciseDecimal, rate: PreciseDecimal) {
+            let share = if units >= self.fixed_debt_units {
+                self.virtual_fixed_debt
+            } else {
+                self.virtual_fixed_debt
+                    .checked_mul(units)
+                    .unwrap()
+                    .checked_div(self.fixed_debt_units)
+                    .unwrap()
+            };
+
+            let (virtual_supply_delta, reserves_delta) = self.__split_interest(owed.checked_sub(share).unwrap());
+            self.virtual_supply = self
+                .virtual_supply
+                .checked_add(virtual_supply_delta)
+                .unwrap()
+                .max(pdec!(0));
+            self.reserves = self.reserves.checked_add(reserves_delta).unwrap().max(pdec!(0));
+
+            let virtual_fixed_debt = self.virtual_fixed_debt.checked_sub(share).unwrap();
+            let fixed_debt_units = self.fixed_debt_units.checked_sub(units).unwrap();
+
+            if fixed_debt_units <= pdec!(0) || virtual_fixed_debt <= pdec!(0) {
+                self.virtual_fixed_debt = pdec!(0);
+                self.fixed_debt_units = pdec!(0);
+                self.fixed_rate = pdec!(0);
+                return;
+            }
+
+            self.fixed_rate = self
+                .fixed_rate
+                .checked_mul(self.virtual_fixed_debt)
+                .unwrap()
+                .checked_sub(rate.checked_mul(share).unwrap())
+                .unwrap()
+                .checked_div(virtual_fixed_debt)
+                .unwrap()
+                .max(pdec!(0));
+            self.virtual_fixed_debt = virtual_fixed_debt;
+            self.fixed_debt_units = fixed_debt_units;
+        }
+
+        fn __verify_state(&self) -> ClusterStateReport {
+            let mut violations: Vec<String> = Vec::new();
+
+            // Non-negativity
+            for (name, value) in [
+                ("supply", self.supply),
+                ("supply units", self.supply_units),
+                ("virtual supply", self.virtual_supply),
+                ("debt", self.debt),
+                ("debt units", self.debt_units),
+                ("virtual debt", self.virtual_debt),
+                ("fixed debt", self.fixed_debt),
+                ("fixed debt units", self.fixed_debt_units),
+                ("virtual fixed debt", self.virtual_fixed_debt),
+                ("reserves", self.reserves),
+                ("provided liquidity", self.provided_liquidity),
+            ] {
+                if value < pdec!(0) {
+                    violations.push(format!("Negative {}: {}", name, value));
+                }
+            }
+
+            // liquidity + flash loans + debt = supply + provided liquidity
+            let assets = PreciseDecimal::from(self.liquidity.amount())
+                .checked_add(self.flash_loaned)
+                .unwrap()
+                .checked_add(self.__total_debt())
+                .unwrap();
+            let liabilities = self.supply.checked_add(self.provided_liquidity).unwrap();
+            if assets.checked_sub(liabilities).unwrap().checked_abs().unwrap() > STATE_TOLERANCE {
+                violations.push(format!(
+                    "Liquidity ({}) + flash loans ({}) + debt ({}) + fixed debt ({}) does not reconcile with supply ({}) + provided liquidity ({})",
+                    self.liquidity.amount(),
+                    self.flash_loaned,
+                    self.debt,
+                    self.fixed_debt,
+                    self.supply,
+                    self.provided_liquidity
+                ));
+            }
+
+            // Units are zero iff virtual amounts are zero
+            if (self.supply_units == pdec!(0)) != (self.virtual_supply == pdec!(0)) {
+                violations.push(format!(
+                    "Supply units ({}) and virtual supply ({}) disagree on being zero",
+                    self.supply_units, self.virtual_supply
+                ));
+            }
+            if (self.debt_units == pdec!(0)) != (self.virtual_debt == pdec!(0)) {
+                violations.push(format!(
+                    "Debt units ({}) and virtual debt ({}) disagree on being zero",
+                    self.debt_units, self.virtual_debt
+                ));
+            }
+            if (self.fixed_debt_units == pdec!(0)) != (self.virtual_fixed_debt == pdec!(0)) {
+                violations.push(format!(
+                    "Fixed debt units ({}) and virtual fixed debt ({}) disagree on being zero",
+                    self.fixed_debt_units, self.virtual_fixed_debt
+                ));
+            }
+
+            // Ratios are positive; they are finite by construction, as all arithmetic is checked
+            if self.virtual_supply > pdec!(0) && self.supply_units <= pdec!(0) {
+                violations.push(format!("Non-positive supply ratio; units: {}", self.supply_units));
+            }
+            if self.virtual_debt > pdec!(0) && self.debt_units <= pdec!(0) {
+                violations.push(format!("Non-positive debt ratio; units: {}", self.debt_units));
+            }
+
+            // Tracked supply units match the resource's total supply
+            if let Some(total_supply) = self.supply_unit_manager.total_supply() {
+                if PreciseDecimal::from(total_supply) != self.supply_units {
+                    violations.push(format!(
+                        "Supply units ({}) do not match the supply unit total supply ({})",
+                        self.supply_units, total_supply
+                    ));
+                }
+            }
+
+            // Tracked debt units match the resource's total supply
+            if let Some(total_supply) = self.debt_unit_manager.total_supply() {
+                if PreciseDecimal::from(total_supply) != self.debt_units {
+                    violations.push(format!(
+                        "Debt units ({}) do not match the debt unit total supply ({})",
+                        self.debt_units, total_supply
+                    ));
+                }
+            }
+
+            // Tracked fixed-rate loans match the loan resource's total supply, and carry all fixed-rate debt
+            if let Some(total_supply) = self.fixed_loan_manager.total_supply() {
+                if total_supply != Decimal::from(self.fixed_loans) {
+                    violations.push(format!(
+                        "Fixed-rate loans ({}) do not match the loan total supply ({})",
+                        self.fixed_loans, total_supply
+                    ));
+                }
+            }
+            if self.fixed_loans == 0 && (self.fixed_debt != pdec!(0) || self.virtual_fixed_debt != pdec!(0)) {
+                violations.push(format!(
+                    "Fixed debt ({}) and virtual fixed debt ({}) remain without any fixed-rate loans",
+                    self.fixed_debt, self.virtual_fixed_debt
+                ));
+            }
+
+            // Tracked withdrawal claims match the claim resource's total supply
+            if let Some(total_supply) = self.claim_manager.total_supply() {
+                if total_supply != Decimal::from(self.withdrawal_claims) {
+                    violations.push(format!(
+                        "Withdrawal claims ({}) do not match the claim total supply ({})",
+                        self.withdrawal_claims, total_supply
+                    ));
+                }
+            }
+
+            ClusterStateReport { at: now(), valid: violations.is_empty(), violations }
+        }
+
+        fn __assert_state_legal(&self) {
+            let report = self.__verify_state();
+            assert!(report.valid, "Illegal cluster state: {:?}", report.violations);
+        }
+
+        fn __assert_operation_allowed(&self, operation: ClusterOperation) {
+            assert!(
+                self.operations.is_allowed(&operation, &self.status),
+                "Operation {:?} is not allowed; cluster is {:?} with operations {:?}",
+                operation,
+                self.status,
+                self.operations
+            );
+        }
+
+        fn __assert_within_supply_cap(&self, amount: Decimal) {
+            if let Some(cap) = self.supply_cap {
+                let new_supply = self.virtual_supply.checked_add(amount).unwrap();
+                assert!(
+                    new_supply <= PreciseDecimal::from(cap),
+                    "Supply cap exceeded; supplying {} would bring supply to {}, above the cap of {}",
+                    amount,
+                    new_supply,
+                    cap
+                );
+            }
+        }
+
+        fn __assert_within_borrow_cap(&self, amount: Decimal) {
+            if let Some(cap) = self.borrow_cap {
+                let new_debt = self
+                    .virtual_debt
+                    .checked_add(self.virtual_fixed_debt)
+                    .unwrap()
+                    .checked_add(amount)
+                    .unwrap();
+                assert!(
+                    new_debt <= PreciseDecimal::from(cap),
+                    "Borrow cap exceeded; borrowing {} would bring debt to {}, above the cap of {}",
+                    amount,
+                    new_debt,
+                    cap
+                );
+            }
+        }
+
+        /// Fetches the borrow and supply APRs from the linked interest model, or the built-in curve if none is linked.
+        ///
+        /// The returned supply APR is net of the reserve factor, and quoted for reporting only; suppliers accrue the
+        /// interest actually paid by borrowers, see `__interest_deltas`.
+        fn __get_rates(&self, elapsed: i64) -> (PreciseDecimal, PreciseDecimal) {
+            let (apr_debt, apr_supply) = match self.interest_model_address {
+                Some(address) => {
+                    let model: Global<AnyComponent> = address.into();
+                    model.call_raw::<(PreciseDecimal, PreciseDecimal)>(
+                        "get_rates",
+                        scrypto_args!(self.supply, self.__total_debt(), self.liquidity.amount(), elapsed),
+                    )
+                }
+                None => self
+                    .interest_model
+                    .get_rates(utilization(self.supply, self.__total_debt())),
+            };
+
+            assert!(apr_debt >= pdec!(0), "Interest model returned a negative borrow rate");
+            assert!(apr_supply >= pdec!(0), "Interest model returned a negative supply rate");
+            assert!(
+                apr_supply <= apr_debt,
+                "Interest model returned a supply rate above the borrow rate"
+            );
+
+            let apr_supply = apr_supply
+                .checked_mul(pdec!(1).checked_sub(self.reserve_factor).unwrap())
+                .unwrap();
+
+            (apr_debt, apr_supply)
+        }
+
+        /// Records the current rates and ratios into the history, evicting the oldest snapshot once it is full.
+        ///
+        /// At most one snapshot is recorded per `interest_tick_interval`, so busy clusters do not write to the history
+        /// on every operation.
+        fn __record_snapshot(&mut self) {
+            let at = now();
+            if self.history_length > 0 && at - self.history_end < self.interest_tick_interval {
+                return;
+            }
+
+            let snapshot = ClusterSnapshot {
+                at,
+                supply_ratio: self.get_ratio(ClusterLayer::Supply),
+                debt_ratio: self.get_ratio(ClusterLayer::Debt),
+                borrow_rate: self.apr,
+                supply_rate: self.supply_apr,
+                utilization: utilization(self.supply, self.__total_debt()),
+            };
+
+            // Without a tick interval, operations within the same second overwrite each other's snapshot
+            if self.history.get(&at).is_some() {
+                self.history.insert(at, snapshot);
+                return;
+            }
+
+            if self.history_length == 0 {
+                self.history_start = at;
+            }
+
+            self.history.insert(at, snapshot);
+            self.history_length += 1;
+            self.history_end = at;
+
+            if self.history_length > HISTORY_LENGTH {
+                self.history.remove(&self.history_start);
+                self.history_length -= 1;
+
+                self.history_start = self
+                    .history
+                    .range(self.history_start..i64::MAX)
+                    .next()
+                    .map(|(next, _, _)| next)
+                    .unwrap_or(at);
+            }
+        }
+
+        /// Returns the reserves that can be paid out without touching supplied funds, bounded by the available liquidity.
+        fn __collectable_reserves(&self) -> Decimal {
+            // Assets left over once every supplier is paid out; reserves can never be collected beyond this
+            let surplus = PreciseDecimal::from(self.liquidity.amount())
+                .checked_add(self.virtual_debt)
+                .unwrap()
+                .checked_add(self.virtual_fixed_debt)
+                .unwrap()
+                .checked_sub(self.virtual_supply)
+                .unwrap()
+                .max(pdec!(0));
+
+            round_down(self.reserves.min(surplus)).min(self.liquidity.amount())
+        }
+
+        /// Updates the reported rates for the current state of the cluster, without accruing interest, and records them
+        /// into the history.
+        fn __refresh_rates(&mut self) {
+            let (apr_debt, apr_supply) = self.__get_rates(0);
+            self.apr = apr_debt;
+            self.supply_apr = apr_supply;
+
+            self.__record_snapshot();
+        }
+
+        /// Returns the virtual supply and debt including the interest accrued since the last tick, without accruing it.
+        fn __pending_virtual(&self) -> (PreciseDecimal, PreciseDecimal) {
+            let interval = now() - self.apr_ticked;
+            let delta_time = PreciseDecimal::from(interval)
+                .checked_div(PreciseDecimal::from(YEAR_IN_SECONDS))
+                .unwrap();
+
+            let (apr_debt, _) = self.__get_rates(interval);
+            let (virtual_debt_delta, _, virtual_supply_delta, _) = self.__interest_deltas(apr_debt, delta_time);
+
+            (
+                self.virtual_supply.checked_add(virtual_supply_delta).unwrap(),
+                self.virtual_debt.checked_add(virtual_debt_delta).unwrap(),
+            )
+        }
+
+        /// Returns the interest accrued over `delta_time` years at the given borrow APR, as the changes to the virtual
+        /// debt, virtual fixed-rate debt, virtual supply and reserves.
+        ///
+        /// Variable-rate debt accrues at the borrow APR, and fixed-rate debt at the locked rates. Suppliers earn exactly
+        /// the interest paid on both, less the reserve factor, which accrues to the reserves.
+        fn __interest_deltas(
+            &self,
+            apr_debt: PreciseDecimal,
+            delta_time: PreciseDecimal,
+        ) -> (PreciseDecimal, PreciseDecimal, PreciseDecimal, PreciseDecimal) {
+            // Interest compounds continuously, so the result is invariant to the tick frequency
+            let virtual_debt_delta = self // change to virtual_debt
+                .virtual_debt
+                .checked_mul(compound(apr_debt, delta_time).checked_sub(pdec!(1)).unwrap())
+                .unwrap();
+            let virtual_fixed_debt_delta = self // change to virtual_fixed_debt, at the locked rates
+                .virtual_fixed_debt
+                .checked_mul(compound(self.fixed_rate, delta_time).checked_sub(pdec!(1)).unwrap())
+                .unwrap();
+
+            let interest_paid = virtual_debt_delta.checked_add(virtual_fixed_debt_delta).unwrap();
+            let (virtual_supply_delta, reserves_delta) = self.__split_interest(interest_paid);
+
+            (
+                virtual_debt_delta,
+                virtual_fixed_debt_delta,
+                virtual_supply_delta,
+                reserves_delta,
+            )
+        }
+
+        /// Splits interest paid by borrowers between suppliers and the protocol, which keeps all of it without suppliers;
+        /// returns the changes to the virtual supply and reserves.
+        fn __split_interest(&self, interest: PreciseDecimal) -> (PreciseDecimal, PreciseDecimal) {
+            let virtual_supply_delta = if self.supply_units == pdec!(0) {
+                pdec!(0)
+            } else {
+                interest
+                    .checked_mul(pdec!(1).checked_sub(self.reserve_factor).unwrap())
+                    .unwrap()
+            };
+
+            (
+                virtual_supply_delta,
+                interest.checked_sub(virtual_supply_delta).unwrap(),
+            )
+        }
+
+        /// Accrues interest since the last tick; returns the reserves accrued.
+        fn __tick_interest(&mut self) -> PreciseDecimal {
+            let reserves_delta = self.__accrue_interest();
+            self.apr_ticked = now();
+
+            reserves_delta
+        }
+
+        /// Accrues interest since the last tick; returns the reserves accrued.
+        fn __accrue_interest(&mut self) -> PreciseDecimal {
+            let interval = now() - self.apr_ticked;
+            let delta_time = PreciseDecimal::from(interval) // I / t_y
+                .checked_div(PreciseDecimal::from(YEAR_IN_SECONDS))
+                .unwrap();
+
+            info!("Delta time is {}", delta_time);
+
+            // Utilization only changes on supply/borrow operations, so the rates are constant over the ticked interval
+            let (apr_debt, apr_supply) = self.__get_rates(interval);
+
+            info!(
+                "Utilization is {}; APR debt is {}, APR supply is {}",
+                utilization(self.supply, self.__total_debt()),
+                apr_debt,
+                apr_supply
+            );
+
+            let (virtual_debt_delta, virtual_fixed_debt_delta, virtual_supply_delta, reserves_delta) =
+                self.__interest_deltas(apr_debt, delta_time);
+
+            info!(
+                "Debt increased by {}, fixed debt by {}, supply by {} and reserves by {}",
+                virtual_debt_delta, virtual_fixed_debt_delta, virtual_supply_delta, reserves_delta
+            );
+
+            let (old_virtual_supply, old_virtual_debt, old_virtual_fixed_debt) =
+                (self.virtual_supply, self.virtual_debt, self.virtual_fixed_debt);
 
             self.virtual_debt = self.virtual_debt.checked_add(virtual_debt_delta).unwrap();
+            self.virtual_fixed_debt = self.virtual_fixed_debt.checked_add(virtual_fixed_debt_delta).unwrap();
             self.virtual_supply = self.virtual_supply.checked_add(virtual_supply_delta).unwrap();
+            self.reserves = self.reserves.checked_add(reserves_delta).unwrap();
+
+            self.apr = apr_debt;
+            self.supply_apr = apr_supply;
+
+            Runtime::emit_event(InterestTickEvent {
+                elapsed: interval,
+                old_virtual_supply,
+                new_virtual_supply: self.virtual_supply,
+                old_virtual_debt,
+                new_virtual_debt: self.virtual_debt,
+                old_virtual_fixed_debt,
+                new_virtual_fixed_debt: self.virtual_fixed_debt,
+                borrow_rate: apr_debt,
+                supply_rate: apr_supply,
+                reserves_delta,
+            });
+
+            reserves_delta
         }
     }
 }
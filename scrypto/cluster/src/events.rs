@@ -0,0 +1,80 @@
+/* ------------------ Imports ----------------- */
+use scrypto::prelude::*;
+
+/* ------------------ Cluster ----------------- */
+//. Loss management
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct BadDebtAbsorbedEvent {
+    pub debt_units: Decimal,
+    pub fixed_rate_loan: Option<NonFungibleLocalId>,
+    pub written_off: PreciseDecimal,
+    pub covered_by_reserves: PreciseDecimal,
+    pub socialized: PreciseDecimal,
+    pub supply_ratio: PreciseDecimal,
+}
+
+//. Position operations
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct ClusterSupplyEvent {
+    pub amount: Decimal,
+    pub units: Decimal,
+    pub supply_ratio: PreciseDecimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct ClusterWithdrawEvent {
+    pub amount: Decimal,
+    pub units: Decimal,
+    pub supply_ratio: PreciseDecimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct ClusterBorrowEvent {
+    pub amount: Decimal,
+    pub debt_units: Decimal, // Zero for fixed-rate loans
+    pub fixed_rate_loan: Option<NonFungibleLocalId>,
+    pub rate: PreciseDecimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct ClusterRepayEvent {
+    pub amount: Decimal,
+    pub debt_units: Decimal, // Zero for fixed-rate loans
+    pub fixed_rate_loan: Option<NonFungibleLocalId>,
+}
+
+//. Liquidity management
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct LiquidityProvidedEvent {
+    pub amount: Decimal,
+    pub provided_liquidity: PreciseDecimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct LiquidityWithdrawnEvent {
+    pub amount: Decimal,
+    pub provided_liquidity: PreciseDecimal,
+}
+
+//. Interest
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct InterestTickEvent {
+    pub elapsed: i64, // seconds
+
+    pub old_virtual_supply: PreciseDecimal,
+    pub new_virtual_supply: PreciseDecimal,
+    pub old_virtual_debt: PreciseDecimal,
+    pub new_virtual_debt: PreciseDecimal,
+    pub old_virtual_fixed_debt: PreciseDecimal,
+    pub new_virtual_fixed_debt: PreciseDecimal,
+
+    pub borrow_rate: PreciseDecimal,
+    pub supply_rate: PreciseDecimal,
+    pub reserves_delta: PreciseDecimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct KeeperRewardEvent {
+    pub amount: Decimal,
+    pub elapsed: i64, // seconds
+}
@@ -1,2 +1,12 @@
 mod cluster;
+mod events;
 mod utils;
+
+pub use crate::cluster::{
+    ClusterConfig, ClusterLayer, ClusterOperation, ClusterOperations, ClusterSnapshot, ClusterState,
+    ClusterStateReport, ClusterStatus, FixedRateLoan, FlashLoanReceipt, InterestRateModel, Rounding, WithdrawalClaim,
+};
+pub use crate::events::{
+    BadDebtAbsorbedEvent, ClusterBorrowEvent, ClusterRepayEvent, ClusterSupplyEvent, ClusterWithdrawEvent,
+    InterestTickEvent, KeeperRewardEvent, LiquidityProvidedEvent, LiquidityWithdrawnEvent,
+};
@@ -1,14 +1,107 @@
 /* ------------------ Imports ----------------- */
+pub use lattic3_interest_curve::utilization;
 use scrypto::prelude::*;
 
 /* ----------------- Utilities ---------------- */
-pub const YEAR_IN_SECONDS: i64 = 31_622_400; // Expected 35_536_000, but from resim testing found 31_622_400 to be representative of a year
+/// Length of a Julian year (365.25 days) in seconds; APRs are expressed per year of this length.
+pub const YEAR_IN_SECONDS: i64 = 31_557_600;
+
+/// Tolerance used when reconciling the cluster's tracked amounts against each other.
+pub const STATE_TOLERANCE: PreciseDecimal = pdec!(0.000000000000000001);
+
+/// Maximum number of Taylor series terms evaluated by `compound`; at x <= 1 the truncation error is below 1e-32.
+pub const COMPOUND_TERMS: u32 = 30;
+
+/// Minimum amount of the first supply to a cluster, which sets the initial supply ratio.
+pub const MINIMUM_INITIAL_SUPPLY: Decimal = dec!(1);
+
+/// Supply units permanently locked in the cluster on its first supply, so the supply ratio can never be reset or inflated
+/// from a near-empty cluster.
+pub const DEAD_UNITS: Decimal = dec!(0.001);
+
+/// Default share of supply interest withheld by the protocol as reserves.
+pub const DEFAULT_RESERVE_FACTOR: PreciseDecimal = pdec!(0.1);
+
+/// Default fee charged on flash loans, as a share of the borrowed amount.
+pub const DEFAULT_FLASH_LOAN_FEE: PreciseDecimal = pdec!(0.0009);
+
+/// Default premium over the variable borrow rate charged on fixed-rate loans.
+pub const DEFAULT_FIXED_RATE_PREMIUM: PreciseDecimal = pdec!(0.02);
+
+/// Default divergence between a fixed-rate loan's rate and the current fixed-rate offer above which it can be rebalanced.
+pub const DEFAULT_FIXED_REBALANCE_THRESHOLD: PreciseDecimal = pdec!(0.05);
+
+/// Default minimum number of seconds between non-forced interest ticks.
+pub const DEFAULT_INTEREST_TICK_INTERVAL: i64 = 3_600;
+
+/// Default reward paid out of reserves to the caller of a due, non-forced interest tick; disabled unless configured.
+pub const DEFAULT_KEEPER_REWARD: Decimal = dec!(0);
+
+/// Maximum number of queued withdrawal requests visited by a single fill of the withdrawal queue.
+pub const WITHDRAWAL_FILL_LIMIT: u64 = 10;
+
+/// Maximum number of snapshots kept in a cluster's history; the oldest are evicted first.
+pub const HISTORY_LENGTH: u64 = 1_000;
+
+/// Maximum number of snapshots returned by a single `get_history` call.
+pub const HISTORY_PAGE_SIZE: usize = 100;
 
 /// Returns the number of seconds since the Unix epoch, i.e. January 1st, 1970 00:00:00 UTC.
 pub fn now() -> i64 {
     Clock::current_time(TimePrecisionV2::Second).seconds_since_unix_epoch
 }
 
-pub fn trunc(amount: PreciseDecimal) -> Decimal {
-    amount.checked_truncate(RoundingMode::ToNearestMidpointToEven).unwrap()
+/// Rounds `amount` down to a `Decimal`; used for units minted on supply, and amounts paid out by the cluster.
+pub fn round_down(amount: PreciseDecimal) -> Decimal {
+    amount.checked_truncate(RoundingMode::ToNegativeInfinity).unwrap()
+}
+
+/// Rounds `amount` up to a `Decimal`; used for units burnt for a requested amount, debt units minted on borrow, and
+/// amounts owed to the cluster.
+pub fn round_up(amount: PreciseDecimal) -> Decimal {
+    amount.checked_truncate(RoundingMode::ToPositiveInfinity).unwrap()
+}
+
+/// Returns the growth factor of continuously compounding `rate` (an APR) over `delta_time` (in years), i.e. e^(rt).
+///
+/// The exponent is halved until it is at most 1, evaluated with a bounded Taylor expansion, and then squared back up.
+/// Since e^(r * t1) * e^(r * t2) = e^(r * (t1 + t2)), accrual does not depend on how often interest is ticked.
+///
+/// # Panics
+/// * If `rate` or `delta_time` is negative.
+/// * If the result overflows a `PreciseDecimal`.
+pub fn compound(rate: PreciseDecimal, delta_time: PreciseDecimal) -> PreciseDecimal {
+    assert!(rate >= pdec!(0), "Rate must be non-negative");
+    assert!(delta_time >= pdec!(0), "Delta time must be non-negative");
+
+    let mut x = rate.checked_mul(delta_time).unwrap();
+
+    // Range reduction; x / 2^k <= 1 keeps the series short and accurate
+    let mut halvings = 0u32;
+    while x > pdec!(1) {
+        x = x.checked_div(pdec!(2)).unwrap();
+        halvings += 1;
+    }
+
+    // e^x = sum(x^n / n!)
+    let mut result = pdec!(1);
+    let mut term = pdec!(1);
+    for n in 1..=COMPOUND_TERMS {
+        term = term
+            .checked_mul(x)
+            .unwrap()
+            .checked_div(PreciseDecimal::from(n))
+            .unwrap();
+        if term == pdec!(0) {
+            break;
+        }
+
+        result = result.checked_add(term).unwrap();
+    }
+
+    for _ in 0..halvings {
+        result = result.checked_mul(result).unwrap();
+    }
+
+    result
 }
@@ -0,0 +1,31 @@
+/* ------------------ Imports ----------------- */
+use scrypto::prelude::*;
+
+/* ----------- Fixed Interest Model ----------- */
+// Test-only interest model which always returns the rates it was instantiated with.
+#[blueprint]
+mod lattic3_fixed_interest_model {
+    struct FixedInterestModel {
+        borrow_rate: PreciseDecimal,
+        supply_rate: PreciseDecimal,
+    }
+
+    impl FixedInterestModel {
+        pub fn instantiate(borrow_rate: PreciseDecimal, supply_rate: PreciseDecimal) -> Global<FixedInterestModel> {
+            FixedInterestModel { borrow_rate, supply_rate }
+                .instantiate()
+                .prepare_to_globalize(OwnerRole::None)
+                .globalize()
+        }
+
+        pub fn get_rates(
+            &self,
+            _supply: PreciseDecimal,
+            _debt: PreciseDecimal,
+            _liquidity: Decimal,
+            _elapsed: i64,
+        ) -> (PreciseDecimal, PreciseDecimal) {
+            (self.borrow_rate, self.supply_rate)
+        }
+    }
+}
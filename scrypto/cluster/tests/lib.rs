@@ -1,10 +1,16 @@
 /* ------------------ Imports ----------------- */
+use lattic3_cluster::{
+    ClusterConfig, ClusterLayer, ClusterOperation, ClusterSnapshot, ClusterState, ClusterStateReport, ClusterStatus,
+    ClusterSupplyEvent, InterestTickEvent, Rounding,
+};
 use scrypto_test::prelude::*;
 
 /* ---------------- Test Setup ---------------- */
 // Test config
 const LOG_TX: bool = true;
 
+const UNIX_2024: i64 = 1704067200;
+
 // Struct to hold account data
 struct Account {
     public_key: Secp256k1PublicKey,
@@ -40,12 +46,61 @@ fn now(ledger: &mut LedgerSimulator<NoExtension, InMemorySubstateDatabase>) -> i
         .seconds_since_unix_epoch
 }
 
+/// Checks that `a` and `b` differ by less than `tolerance`
+fn within(a: PreciseDecimal, b: PreciseDecimal, tolerance: PreciseDecimal) -> bool {
+    a.checked_sub(b).unwrap().checked_abs().unwrap() < tolerance
+}
+
 /* ------------- Helper Manifests ------------- */
+/// Initialise default state for unit tests; an XRD cluster owned by the main account
+fn setup() -> (
+    LedgerSimulator<NoExtension, InMemorySubstateDatabase>, // Ledger simulation
+    PackageAddress,                                         // Package
+    (Account, Account),                                     // Accounts: Main, User 1
+    (ResourceAddress, ResourceAddress),                     // Badges: Owner, Admin
+    (ComponentAddress, ResourceAddress),                    // XRD cluster and its supply unit
+) {
+    //. Simulation Setup
+    let mut ledger: LedgerSimulator<NoExtension, InMemorySubstateDatabase> = LedgerSimulatorBuilder::new().build();
+    ledger.advance_to_round_at_timestamp(Round::of(1), UNIX_2024);
+
+    //. Account Setup
+    let main_account = Account::new(&mut ledger);
+    let user_account = Account::new(&mut ledger);
+
+    //. Create badges
+    let owner_badge = create_fungible(&mut ledger, dec!(1), "Cluster Owner Badge", &main_account);
+    let admin_badge = create_fungible(&mut ledger, dec!(1), "Cluster Admin Badge", &main_account);
+
+    //. Package Setup
+    let package_address = ledger.compile_and_publish(this_package!());
+
+    //. Instantiate
+    let xrd_cluster = instantiate(
+        &mut ledger,
+        package_address,
+        &main_account,
+        XRD,
+        None,
+        rule!(require(owner_badge)),
+        rule!(require(admin_badge)),
+    );
+
+    (
+        ledger,
+        package_address,
+        (main_account, user_account),
+        (owner_badge, admin_badge),
+        xrd_cluster,
+    )
+}
+
 fn instantiate(
     ledger: &mut LedgerSimulator<NoExtension, InMemorySubstateDatabase>,
     package_address: PackageAddress,
     owner_account: &Account,
     resource: ResourceAddress,
+    config: Option<ClusterConfig>,
     cluster_owner_rule: AccessRule,
     cluster_admin_rule: AccessRule,
 ) -> (ComponentAddress, ResourceAddress) {
@@ -55,7 +110,7 @@ fn instantiate(
             package_address,
             "Cluster",
             "instantiate",
-            manifest_args!(resource, cluster_owner_rule, cluster_admin_rule),
+            manifest_args!(resource, config, cluster_owner_rule, cluster_admin_rule),
         )
         .deposit_batch(owner_account.address)
         .build();
@@ -65,7 +120,7 @@ fn instantiate(
     receipt.expect_commit_success();
 
     let component = receipt.expect_commit(true).new_component_addresses()[0];
-    let unit = receipt.expect_commit(true).new_resource_addresses()[0];
+    let unit = get_cluster_state(ledger, component, owner_account).supply_unit;
 
     (component, unit)
 }
@@ -96,6 +151,183 @@ fn create_fungible(
     receipt.expect_commit(true).new_resource_addresses()[0]
 }
 
+fn get_cluster_state(
+    ledger: &mut LedgerSimulator<NoExtension, InMemorySubstateDatabase>,
+    cluster: ComponentAddress,
+    caller: &Account,
+) -> ClusterState {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(cluster, "get_cluster_state", manifest_args!())
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![caller.nf_global_id()]);
+
+    log_tx("get_cluster_state", &receipt);
+    receipt.expect_commit_success().output(1)
+}
+
+/// Supplies `amount` XRD to the cluster as its admin, depositing the supply units into the caller's account
+fn supply(
+    ledger: &mut LedgerSimulator<NoExtension, InMemorySubstateDatabase>,
+    cluster: ComponentAddress,
+    caller: &Account,
+    admin_badge: ResourceAddress,
+    amount: Decimal,
+) -> TransactionReceiptV1 {
+    supply_resource(ledger, cluster, caller, admin_badge, XRD, amount)
+}
+
+/// Supplies `amount` of `resource` to the cluster as its admin, depositing the supply units into the caller's account
+fn supply_resource(
+    ledger: &mut LedgerSimulator<NoExtension, InMemorySubstateDatabase>,
+    cluster: ComponentAddress,
+    caller: &Account,
+    admin_badge: ResourceAddress,
+    resource: ResourceAddress,
+    amount: Decimal,
+) -> TransactionReceiptV1 {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(caller.address, admin_badge, dec!(1))
+        .withdraw_from_account(caller.address, resource, amount)
+        .take_from_worktop(resource, amount, "bucket_supply")
+        .call_method_with_name_lookup(cluster, "supply", |lookup| (lookup.bucket("bucket_supply"),))
+        .deposit_batch(caller.address)
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![caller.nf_global_id()]);
+
+    log_tx("supply", &receipt);
+    receipt
+}
+
+/// Borrows `amount` XRD from the cluster as its admin, depositing the borrowed resource into the caller's account
+fn borrow(
+    ledger: &mut LedgerSimulator<NoExtension, InMemorySubstateDatabase>,
+    cluster: ComponentAddress,
+    caller: &Account,
+    admin_badge: ResourceAddress,
+    amount: Decimal,
+) -> TransactionReceiptV1 {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(caller.address, admin_badge, dec!(1))
+        .call_method(cluster, "borrow", manifest_args!(amount))
+        .deposit_batch(caller.address)
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![caller.nf_global_id()]);
+
+    log_tx("borrow", &receipt);
+    receipt
+}
+
+/// Withdraws `units` supply units from the caller's account and redeems them with the cluster
+fn withdraw(
+    ledger: &mut LedgerSimulator<NoExtension, InMemorySubstateDatabase>,
+    cluster: ComponentAddress,
+    caller: &Account,
+    admin_badge: ResourceAddress,
+    unit: ResourceAddress,
+    units: Decimal,
+) -> TransactionReceiptV1 {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(caller.address, admin_badge, dec!(1))
+        .withdraw_from_account(caller.address, unit, units)
+        .take_from_worktop(unit, units, "bucket_units")
+        .call_method_with_name_lookup(cluster, "withdraw", |lookup| (lookup.bucket("bucket_units"),))
+        .deposit_batch(caller.address)
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![caller.nf_global_id()]);
+
+    log_tx("withdraw", &receipt);
+    receipt
+}
+
+/// Converts between amounts and units with the given rounding; `method` is either `get_units` or `get_amount`
+fn convert(
+    ledger: &mut LedgerSimulator<NoExtension, InMemorySubstateDatabase>,
+    cluster: ComponentAddress,
+    caller: &Account,
+    method: &str,
+    layer: ClusterLayer,
+    value: Decimal,
+    rounding: Rounding,
+) -> Decimal {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(cluster, method, manifest_args!(layer, value, rounding))
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![caller.nf_global_id()]);
+
+    log_tx(method, &receipt);
+    receipt.expect_commit_success().output(1)
+}
+
+fn tick_interest(
+    ledger: &mut LedgerSimulator<NoExtension, InMemorySubstateDatabase>,
+    cluster: ComponentAddress,
+    caller: &Account,
+) {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(cluster, "tick_interest", manifest_args!(true))
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![caller.nf_global_id()]);
+
+    log_tx("tick_interest", &receipt);
+    receipt.expect_commit_success();
+}
+
+fn set_status(
+    ledger: &mut LedgerSimulator<NoExtension, InMemorySubstateDatabase>,
+    cluster: ComponentAddress,
+    caller: &Account,
+    admin_badge: ResourceAddress,
+    status: ClusterStatus,
+) {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(caller.address, admin_badge, dec!(1))
+        .call_method(cluster, "set_status", manifest_args!(status))
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![caller.nf_global_id()]);
+
+    log_tx("set_status", &receipt);
+    receipt.expect_commit_success();
+}
+
+fn verify_state(
+    ledger: &mut LedgerSimulator<NoExtension, InMemorySubstateDatabase>,
+    cluster: ComponentAddress,
+    caller: &Account,
+) -> ClusterStateReport {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(cluster, "verify_state", manifest_args!())
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![caller.nf_global_id()]);
+
+    log_tx("verify_state", &receipt);
+    receipt.expect_commit_success().output(1)
+}
+
+fn get_history(
+    ledger: &mut LedgerSimulator<NoExtension, InMemorySubstateDatabase>,
+    cluster: ComponentAddress,
+    caller: &Account,
+    from: i64,
+    to: i64,
+) -> Vec<ClusterSnapshot> {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(cluster, "get_history", manifest_args!(from, to))
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![caller.nf_global_id()]);
+
+    log_tx("get_history", &receipt);
+    receipt.expect_commit_success().output(1)
+}
+
 /* ------------------- Tests ------------------ */
 /// Basic test to check that Lattic3 instantises correctly
 #[test]
@@ -103,7 +335,6 @@ fn basic_test() -> Result<(), RuntimeError> {
     //. Simulation Setup
     let mut ledger: LedgerSimulator<NoExtension, InMemorySubstateDatabase> = LedgerSimulatorBuilder::new().build();
 
-    const UNIX_2024: i64 = 1704067200;
     const SIX_MONTHS: i64 = 15811200;
 
     ledger.advance_to_round_at_timestamp(Round::of(1), UNIX_2024);
@@ -131,6 +362,7 @@ fn basic_test() -> Result<(), RuntimeError> {
         package_address,
         &main_account,
         XRD,
+        None,
         rule!(require(owner_badge)),
         rule!(require(admin_badge)),
     );
@@ -174,3 +406,1671 @@ fn basic_test() -> Result<(), RuntimeError> {
 
     Ok(())
 }
+
+/// Tests that a linked interest model overrides the built-in curve, and that unlinking restores it
+#[test]
+fn interest_model_link_test() -> Result<(), RuntimeError> {
+    // Deconstruct setup
+    let (mut ledger, _, (main_account, user_account), (_, admin_badge), (xrd_cluster, _)) = setup();
+
+    //. Publish and instantiate the test model
+    let model_package = ledger.compile_and_publish(format!("{}/tests/fixed-interest-model", this_package!()));
+
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_function(
+            model_package,
+            "FixedInterestModel",
+            "instantiate",
+            manifest_args!(pdec!(0.2), pdec!(0.1)),
+        )
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![]);
+
+    log_tx("instantiate_model", &receipt);
+    let model = receipt.expect_commit(true).new_component_addresses()[0];
+
+    //. Linking without the admin badge fails
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(xrd_cluster, "link_interest_model", manifest_args!(model))
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![user_account.nf_global_id()]);
+
+    log_tx("link_interest_model:noperm", &receipt);
+    receipt.expect_commit_failure();
+
+    //. Link the model
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(main_account.address, admin_badge, dec!(1))
+        .call_method(xrd_cluster, "link_interest_model", manifest_args!(model))
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![main_account.nf_global_id()]);
+
+    log_tx("link_interest_model", &receipt);
+    receipt.expect_commit_success();
+
+    let state = get_cluster_state(&mut ledger, xrd_cluster, &main_account);
+    assert_eq!(state.apr, pdec!(0.2), "Borrow rate not taken from the linked model");
+    assert_eq!(
+        state.supply_apr,
+        pdec!(0.1),
+        "Supply rate not taken from the linked model"
+    );
+
+    //. Unlink the model; an empty cluster falls back to the built-in base rate
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(main_account.address, admin_badge, dec!(1))
+        .call_method(xrd_cluster, "unlink_interest_model", manifest_args!())
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![main_account.nf_global_id()]);
+
+    log_tx("unlink_interest_model", &receipt);
+    receipt.expect_commit_success();
+
+    let state = get_cluster_state(&mut ledger, xrd_cluster, &main_account);
+    assert_eq!(state.apr, pdec!(0.02), "Borrow rate not reverted to the built-in curve");
+    assert_eq!(
+        state.supply_apr,
+        pdec!(0),
+        "Supply rate not reverted to the built-in curve"
+    );
+
+    Ok(())
+}
+
+/// Tests that interest accrued in a single tick over a year matches accrual over many smaller ticks
+#[test]
+fn interest_compounding_test() -> Result<(), RuntimeError> {
+    const YEAR: i64 = 31_557_600;
+    const TICKS: i64 = 52;
+
+    let mut states: Vec<ClusterState> = Vec::new();
+
+    for ticks in [1, TICKS] {
+        // Deconstruct setup
+        let (mut ledger, _, (main_account, _), (_, admin_badge), (xrd_cluster, _)) = setup();
+
+        supply(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(1000)).expect_commit_success();
+        borrow(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(500)).expect_commit_success();
+
+        // Borrowing ticks interest, so accrual starts from the same point in both runs
+        let start = now(&mut ledger);
+        for i in 1..=ticks {
+            ledger.advance_to_round_at_timestamp(Round::of((i + 1) as u64), start + YEAR * i / ticks);
+            tick_interest(&mut ledger, xrd_cluster, &main_account);
+        }
+
+        assert_eq!(now(&mut ledger), start + YEAR, "Simulation did not advance by a year");
+        states.push(get_cluster_state(&mut ledger, xrd_cluster, &main_account));
+    }
+
+    let (single, many) = (&states[0], &states[1]);
+    println!("Single tick: {:#?}\nMany ticks: {:#?}", single, many);
+
+    let tolerance = pdec!(0.000000001);
+    assert!(
+        within(single.virtual_debt, many.virtual_debt, tolerance),
+        "Virtual debt depends on tick frequency"
+    );
+    assert!(
+        within(single.virtual_supply, many.virtual_supply, tolerance),
+        "Virtual supply depends on tick frequency"
+    );
+    assert!(
+        within(single.reserves, many.reserves, tolerance),
+        "Reserves depend on tick frequency"
+    );
+
+    assert!(single.virtual_debt > pdec!(500), "Debt did not accrue interest");
+
+    Ok(())
+}
+
+/// Tests that supply and borrow are rejected once they would exceed the cluster's caps
+#[test]
+fn cluster_caps_test() -> Result<(), RuntimeError> {
+    // Deconstruct setup
+    let (mut ledger, _, (main_account, _), (_, admin_badge), (xrd_cluster, _)) = setup();
+
+    //. Set caps
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(main_account.address, admin_badge, dec!(1))
+        .call_method(xrd_cluster, "set_supply_cap", manifest_args!(Some(dec!(100))))
+        .call_method(xrd_cluster, "set_borrow_cap", manifest_args!(Some(dec!(50))))
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![main_account.nf_global_id()]);
+
+    log_tx("set_caps", &receipt);
+    receipt.expect_commit_success();
+
+    let state = get_cluster_state(&mut ledger, xrd_cluster, &main_account);
+    assert_eq!(state.supply_cap, Some(dec!(100)));
+    assert_eq!(state.borrow_cap, Some(dec!(50)));
+
+    //. Supply up to the cap, then beyond it
+    supply(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(60)).expect_commit_success();
+    supply(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(60)).expect_commit_failure();
+
+    //. Borrow up to the cap, then beyond it
+    borrow(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(30)).expect_commit_success();
+    borrow(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(30)).expect_commit_failure();
+
+    Ok(())
+}
+
+/// Tests that frozen and paused clusters, and disabled operations, reject the corresponding operations
+#[test]
+fn cluster_status_test() -> Result<(), RuntimeError> {
+    // Deconstruct setup
+    let (mut ledger, _, (main_account, _), (_, admin_badge), (xrd_cluster, _)) = setup();
+
+    supply(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(100)).expect_commit_success();
+
+    //. Frozen; no new supply or borrows
+    set_status(
+        &mut ledger,
+        xrd_cluster,
+        &main_account,
+        admin_badge,
+        ClusterStatus::Frozen,
+    );
+    assert_eq!(
+        get_cluster_state(&mut ledger, xrd_cluster, &main_account).status,
+        ClusterStatus::Frozen
+    );
+
+    supply(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(10)).expect_commit_failure();
+    borrow(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(10)).expect_commit_failure();
+
+    //. Paused; also no borrows
+    set_status(
+        &mut ledger,
+        xrd_cluster,
+        &main_account,
+        admin_badge,
+        ClusterStatus::Paused,
+    );
+    borrow(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(10)).expect_commit_failure();
+
+    //. Active, but with borrowing toggled off
+    set_status(
+        &mut ledger,
+        xrd_cluster,
+        &main_account,
+        admin_badge,
+        ClusterStatus::Active,
+    );
+
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(main_account.address, admin_badge, dec!(1))
+        .call_method(
+            xrd_cluster,
+            "set_operation_enabled",
+            manifest_args!(ClusterOperation::Borrow, false),
+        )
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![main_account.nf_global_id()]);
+
+    log_tx("set_operation_enabled", &receipt);
+    receipt.expect_commit_success();
+
+    supply(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(10)).expect_commit_success();
+    borrow(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(10)).expect_commit_failure();
+
+    Ok(())
+}
+
+/// Tests that the cluster's invariants hold across supply, borrow, liquidity provision and interest accrual
+#[test]
+fn verify_state_test() -> Result<(), RuntimeError> {
+    // Deconstruct setup
+    let (mut ledger, _, (main_account, user_account), (_, admin_badge), (xrd_cluster, _)) = setup();
+
+    let report = verify_state(&mut ledger, xrd_cluster, &user_account);
+    assert!(report.valid, "Empty cluster is invalid: {:?}", report.violations);
+
+    //. Provide liquidity, supply and borrow
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(main_account.address, admin_badge, dec!(1))
+        .withdraw_from_account(main_account.address, XRD, dec!(100))
+        .take_from_worktop(XRD, dec!(100), "bucket_xrd")
+        .call_method_with_name_lookup(
+            xrd_cluster,
+            "provide_liquidity",
+            |lookup| (lookup.bucket("bucket_xrd"),),
+        )
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![main_account.nf_global_id()]);
+
+    log_tx("provide_liquidity", &receipt);
+    receipt.expect_commit_success();
+
+    supply(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(1000)).expect_commit_success();
+    borrow(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(400)).expect_commit_success();
+
+    //. Accrue interest
+    ledger.advance_to_round_at_timestamp(Round::of(2), UNIX_2024 + 86_400);
+    tick_interest(&mut ledger, xrd_cluster, &main_account);
+
+    let report = verify_state(&mut ledger, xrd_cluster, &user_account);
+    assert!(report.valid, "Cluster is invalid: {:?}", report.violations);
+
+    Ok(())
+}
+
+/// Tests that a supplier entering right before a large tick is minted units at the accrued ratio, and so cannot capture
+/// interest accrued before they entered
+#[test]
+fn accrue_before_supply_test() -> Result<(), RuntimeError> {
+    const YEAR: i64 = 31_557_600;
+
+    // Deconstruct setup
+    let (mut ledger, _, (main_account, _), (_, admin_badge), (xrd_cluster, xrd_unit)) = setup();
+
+    supply(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(1000)).expect_commit_success();
+    borrow(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(500)).expect_commit_success();
+
+    //. Let a year of interest build up without ticking
+    ledger.advance_to_round_at_timestamp(Round::of(2), UNIX_2024 + YEAR);
+
+    //. Enter right before the tick
+    let units_before = ledger.get_component_balance(main_account.address, xrd_unit);
+    supply(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(1000)).expect_commit_success();
+    let minted = ledger.get_component_balance(main_account.address, xrd_unit) - units_before;
+
+    assert!(minted < dec!(1000), "Units were minted at a stale ratio: {}", minted);
+
+    //. Exit immediately
+    let xrd_before = ledger.get_component_balance(main_account.address, XRD);
+    withdraw(&mut ledger, xrd_cluster, &main_account, admin_badge, xrd_unit, minted).expect_commit_success();
+    let withdrawn = ledger.get_component_balance(main_account.address, XRD) - xrd_before;
+
+    assert!(
+        withdrawn <= dec!(1000),
+        "Supplier captured interest accrued before they entered: {}",
+        withdrawn
+    );
+
+    let report = verify_state(&mut ledger, xrd_cluster, &main_account);
+    assert!(report.valid, "Cluster is invalid: {:?}", report.violations);
+
+    Ok(())
+}
+
+/// Tests that a supplier exiting after interest has built up is paid out at the accrued ratio
+#[test]
+fn accrue_before_withdraw_test() -> Result<(), RuntimeError> {
+    const YEAR: i64 = 31_557_600;
+
+    // Deconstruct setup
+    let (mut ledger, _, (main_account, _), (_, admin_badge), (xrd_cluster, xrd_unit)) = setup();
+
+    supply(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(1000)).expect_commit_success();
+    borrow(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(500)).expect_commit_success();
+
+    //. Let a year of interest build up without ticking
+    ledger.advance_to_round_at_timestamp(Round::of(2), UNIX_2024 + YEAR);
+
+    let xrd_before = ledger.get_component_balance(main_account.address, XRD);
+    withdraw(
+        &mut ledger,
+        xrd_cluster,
+        &main_account,
+        admin_badge,
+        xrd_unit,
+        dec!(100),
+    )
+    .expect_commit_success();
+    let withdrawn = ledger.get_component_balance(main_account.address, XRD) - xrd_before;
+
+    assert!(
+        withdrawn > dec!(100),
+        "Units were redeemed at a stale ratio: {}",
+        withdrawn
+    );
+
+    Ok(())
+}
+
+/// Tests that absorbed bad debt is written off and the loss is socialized across lenders
+#[test]
+fn absorb_bad_debt_test() -> Result<(), RuntimeError> {
+    // Deconstruct setup
+    let (mut ledger, _, (main_account, _), (_, admin_badge), (xrd_cluster, xrd_unit)) = setup();
+
+    supply(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(1000)).expect_commit_success();
+    borrow(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(500)).expect_commit_success();
+
+    let debt_unit = get_cluster_state(&mut ledger, xrd_cluster, &main_account).debt_unit;
+    let debt_units = ledger.get_component_balance(main_account.address, debt_unit);
+
+    //. Write off the entire debt
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(main_account.address, admin_badge, dec!(1))
+        .withdraw_from_account(main_account.address, debt_unit, debt_units)
+        .take_from_worktop(debt_unit, debt_units, "bucket_units")
+        .call_method_with_name_lookup(
+            xrd_cluster,
+            "absorb_bad_debt",
+            |lookup| (lookup.bucket("bucket_units"),),
+        )
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![main_account.nf_global_id()]);
+
+    log_tx("absorb_bad_debt", &receipt);
+    receipt.expect_commit_success();
+
+    let state = get_cluster_state(&mut ledger, xrd_cluster, &main_account);
+    println!("State after absorbing bad debt: {:#?}", state);
+
+    assert_eq!(state.debt, pdec!(0));
+    assert_eq!(state.virtual_debt, pdec!(0));
+    assert_eq!(state.supply, pdec!(500));
+    assert!(state.supply_ratio > pdec!(1), "Loss was not socialized across lenders");
+
+    let report = verify_state(&mut ledger, xrd_cluster, &main_account);
+    assert!(report.valid, "Cluster is invalid: {:?}", report.violations);
+
+    //. Lenders can only redeem what is left, less the share of the dead units
+    let units = ledger.get_component_balance(main_account.address, xrd_unit);
+    let xrd_before = ledger.get_component_balance(main_account.address, XRD);
+    withdraw(&mut ledger, xrd_cluster, &main_account, admin_badge, xrd_unit, units).expect_commit_success();
+    let withdrawn = ledger.get_component_balance(main_account.address, XRD) - xrd_before;
+
+    assert!(
+        within(withdrawn.into(), pdec!(499.9995), pdec!(0.000001)),
+        "Withdrew {} instead of the remaining supply",
+        withdrawn
+    );
+
+    Ok(())
+}
+
+/// Tests that absorbing bad debt after lenders withdrew part of their interest never drives the provided liquidity
+/// negative
+#[test]
+fn absorb_bad_debt_after_withdrawal_test() -> Result<(), RuntimeError> {
+    const YEAR: i64 = 31_557_600;
+
+    // Deconstruct setup
+    let (mut ledger, _, (main_account, _), (_, admin_badge), (xrd_cluster, xrd_unit)) = setup();
+
+    supply(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(100)).expect_commit_success();
+    borrow(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(50)).expect_commit_success();
+
+    //. Let interest accrue, and repay part of the debt, so lenders can withdraw their interest
+    ledger.advance_to_round_at_timestamp(Round::of(2), UNIX_2024 + YEAR);
+    tick_interest(&mut ledger, xrd_cluster, &main_account);
+
+    let debt_unit = get_cluster_state(&mut ledger, xrd_cluster, &main_account).debt_unit;
+    let debt_units = ledger.get_component_balance(main_account.address, debt_unit);
+
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(main_account.address, admin_badge, dec!(1))
+        .withdraw_from_account(main_account.address, XRD, dec!(20))
+        .take_from_worktop(XRD, dec!(20), "bucket_repayment")
+        .withdraw_from_account(main_account.address, debt_unit, debt_units)
+        .take_from_worktop(debt_unit, debt_units, "bucket_units")
+        .call_method_with_name_lookup(xrd_cluster, "repay", |lookup| {
+            (lookup.bucket("bucket_repayment"), lookup.bucket("bucket_units"))
+        })
+        .deposit_batch(main_account.address)
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![main_account.nf_global_id()]);
+
+    log_tx("repay", &receipt);
+    receipt.expect_commit_success();
+
+    //. Lenders withdraw 60, including part of their interest
+    let units = convert(
+        &mut ledger,
+        xrd_cluster,
+        &main_account,
+        "get_units",
+        ClusterLayer::Supply,
+        dec!(60),
+        Rounding::Up,
+    );
+    withdraw(&mut ledger, xrd_cluster, &main_account, admin_badge, xrd_unit, units).expect_commit_success();
+
+    //. Write off the remaining debt
+    let debt_units = ledger.get_component_balance(main_account.address, debt_unit);
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(main_account.address, admin_badge, dec!(1))
+        .withdraw_from_account(main_account.address, debt_unit, debt_units)
+        .take_from_worktop(debt_unit, debt_units, "bucket_units")
+        .call_method_with_name_lookup(
+            xrd_cluster,
+            "absorb_bad_debt",
+            |lookup| (lookup.bucket("bucket_units"),),
+        )
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![main_account.nf_global_id()]);
+
+    log_tx("absorb_bad_debt", &receipt);
+    receipt.expect_commit_success();
+
+    let state = get_cluster_state(&mut ledger, xrd_cluster, &main_account);
+    assert_eq!(state.debt, pdec!(0));
+    assert!(
+        state.provided_liquidity >= pdec!(0),
+        "Negative provided liquidity: {}",
+        state.provided_liquidity
+    );
+
+    let report = verify_state(&mut ledger, xrd_cluster, &main_account);
+    assert!(report.valid, "Cluster is invalid: {:?}", report.violations);
+
+    Ok(())
+}
+
+/// Tests that interest ticks are recorded into the cluster's history, and that it can be queried by time range
+#[test]
+fn history_test() -> Result<(), RuntimeError> {
+    const DAY: i64 = 86_400;
+
+    // Deconstruct setup
+    let (mut ledger, _, (main_account, _), (_, admin_badge), (xrd_cluster, _)) = setup();
+
+    supply(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(1000)).expect_commit_success();
+    borrow(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(500)).expect_commit_success();
+
+    //. Tick once a day for five days
+    for day in 1..=5 {
+        ledger.advance_to_round_at_timestamp(Round::of((day + 1) as u64), UNIX_2024 + DAY * day);
+        tick_interest(&mut ledger, xrd_cluster, &main_account);
+    }
+
+    //. A tick within the interval of the last snapshot records nothing
+    ledger.advance_to_round_at_timestamp(Round::of(7), UNIX_2024 + DAY * 5 + 1);
+    tick_interest(&mut ledger, xrd_cluster, &main_account);
+
+    //. Query all snapshots; supply and borrow fall within the same interval, so only the supply is recorded
+    let history = get_history(&mut ledger, xrd_cluster, &main_account, UNIX_2024, i64::MAX);
+    println!("History: {:#?}", history);
+
+    assert_eq!(history.len(), 6);
+    assert_eq!(history[0].utilization, pdec!(0));
+    assert_eq!(history.last().unwrap().at, UNIX_2024 + DAY * 5);
+    assert!(
+        history.windows(2).all(|pair| pair[0].at < pair[1].at),
+        "History is not in order"
+    );
+
+    let last = history.last().unwrap();
+    assert!(last.borrow_rate > pdec!(0), "Borrow rate was not recorded");
+    assert!(
+        within(last.utilization, pdec!(0.5), pdec!(0.01)),
+        "Utilization was not recorded"
+    );
+    assert!(
+        last.supply_ratio < pdec!(1),
+        "Supply ratio did not follow accrued interest"
+    );
+
+    //. Query a sub-range
+    let range = get_history(
+        &mut ledger,
+        xrd_cluster,
+        &main_account,
+        UNIX_2024 + DAY * 2,
+        UNIX_2024 + DAY * 4,
+    );
+
+    assert_eq!(range.len(), 2);
+    assert_eq!(range[0].at, UNIX_2024 + DAY * 2);
+    assert_eq!(range[1].at, UNIX_2024 + DAY * 3);
+
+    Ok(())
+}
+
+/// Tests that a flash loan repaid within the transaction succeeds, with its fee accruing to suppliers and reserves
+#[test]
+fn flash_loan_test() -> Result<(), RuntimeError> {
+    // Deconstruct setup
+    let (mut ledger, _, (main_account, user_account), (_, admin_badge), (xrd_cluster, _)) = setup();
+
+    supply(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(1000)).expect_commit_success();
+
+    let before = get_cluster_state(&mut ledger, xrd_cluster, &main_account);
+    let receipt_resource = before.flash_loan_receipt;
+
+    //. Borrow and repay within the same transaction; the fee on 100 XRD is 0.09 XRD
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(xrd_cluster, "flash_borrow", manifest_args!(dec!(100)))
+        .withdraw_from_account(user_account.address, XRD, dec!(0.09))
+        .take_from_worktop(XRD, dec!(100.09), "bucket_xrd")
+        .take_all_from_worktop(receipt_resource, "bucket_receipt")
+        .call_method_with_name_lookup(xrd_cluster, "flash_repay", |lookup| {
+            (lookup.bucket("bucket_xrd"), lookup.bucket("bucket_receipt"))
+        })
+        .deposit_batch(user_account.address)
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![user_account.nf_global_id()]);
+
+    log_tx("flash_loan", &receipt);
+    receipt.expect_commit_success();
+
+    let after = get_cluster_state(&mut ledger, xrd_cluster, &main_account);
+    println!("State after flash loan: {:#?}", after);
+
+    assert_eq!(after.flash_loaned, dec!(0));
+    assert_eq!(after.liquidity, before.liquidity + dec!(0.09));
+    assert!(
+        after.virtual_supply > before.virtual_supply,
+        "Fee did not accrue to suppliers"
+    );
+    assert!(after.reserves > before.reserves, "Fee did not accrue to reserves");
+
+    let report = verify_state(&mut ledger, xrd_cluster, &main_account);
+    assert!(report.valid, "Cluster is invalid: {:?}", report.violations);
+
+    Ok(())
+}
+
+/// Tests that a flash loan which is not repaid, or is repaid without its fee, aborts the transaction
+#[test]
+fn flash_loan_unrepaid_test() -> Result<(), RuntimeError> {
+    // Deconstruct setup
+    let (mut ledger, _, (main_account, user_account), (_, admin_badge), (xrd_cluster, _)) = setup();
+
+    supply(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(1000)).expect_commit_success();
+
+    let receipt_resource = get_cluster_state(&mut ledger, xrd_cluster, &main_account).flash_loan_receipt;
+
+    //. Keep the loan; the receipt cannot be deposited
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(xrd_cluster, "flash_borrow", manifest_args!(dec!(100)))
+        .deposit_batch(user_account.address)
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![user_account.nf_global_id()]);
+
+    log_tx("flash_loan_unrepaid", &receipt);
+    receipt.expect_commit_failure();
+
+    //. Repay only the principal
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(xrd_cluster, "flash_borrow", manifest_args!(dec!(100)))
+        .take_from_worktop(XRD, dec!(100), "bucket_xrd")
+        .take_all_from_worktop(receipt_resource, "bucket_receipt")
+        .call_method_with_name_lookup(xrd_cluster, "flash_repay", |lookup| {
+            (lookup.bucket("bucket_xrd"), lookup.bucket("bucket_receipt"))
+        })
+        .deposit_batch(user_account.address)
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![user_account.nf_global_id()]);
+
+    log_tx("flash_loan_underpaid", &receipt);
+    receipt.expect_commit_failure();
+
+    let state = get_cluster_state(&mut ledger, xrd_cluster, &main_account);
+    assert_eq!(state.liquidity, dec!(1000));
+    assert_eq!(state.flash_loaned, dec!(0));
+
+    Ok(())
+}
+
+/// Tests that a cluster can be exported and taken over by a new cluster, keeping the same supply units
+#[test]
+fn migration_test() -> Result<(), RuntimeError> {
+    // Deconstruct setup
+    let (mut ledger, package_address, (main_account, _), (owner_badge, admin_badge), (xrd_cluster, xrd_unit)) = setup();
+
+    supply(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(1000)).expect_commit_success();
+    borrow(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(400)).expect_commit_success();
+
+    ledger.advance_to_round_at_timestamp(Round::of(2), UNIX_2024 + 86_400);
+
+    //. Export the old cluster
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(main_account.address, owner_badge, dec!(1))
+        .call_method(xrd_cluster, "export_state", manifest_args!())
+        .deposit_batch(main_account.address)
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![main_account.nf_global_id()]);
+
+    log_tx("export_state", &receipt);
+    let (_, _, state): (Own, Own, ClusterState) = receipt.expect_commit_success().output(2);
+    println!("Exported state: {:#?}", state);
+
+    // The old cluster is retired
+    supply(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(10)).expect_commit_failure();
+
+    //. Instantiate the new cluster from the exported state
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .withdraw_from_account(main_account.address, XRD, state.liquidity)
+        .take_from_worktop(XRD, state.liquidity, "bucket_liquidity")
+        .withdraw_from_account(main_account.address, state.authority, dec!(1))
+        .take_from_worktop(state.authority, dec!(1), "bucket_authority")
+        .call_function_with_name_lookup(package_address, "Cluster", "instantiate_from_state", |lookup| {
+            (
+                state.clone(),
+                lookup.bucket("bucket_liquidity"),
+                lookup.bucket("bucket_authority"),
+                rule!(require(owner_badge)),
+                rule!(require(admin_badge)),
+            )
+        })
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![main_account.nf_global_id()]);
+
+    log_tx("instantiate_from_state", &receipt);
+    let new_cluster = receipt.expect_commit_success().new_component_addresses()[0];
+
+    let migrated = get_cluster_state(&mut ledger, new_cluster, &main_account);
+    println!("Migrated state: {:#?}", migrated);
+
+    assert_eq!(migrated.supply_unit, xrd_unit);
+    assert_eq!(migrated.debt_unit, state.debt_unit);
+    assert_eq!(migrated.liquidity, state.liquidity);
+    assert_eq!(migrated.supply_units, state.supply_units);
+    assert_eq!(migrated.virtual_supply, state.virtual_supply);
+    assert_eq!(migrated.debt_units, state.debt_units);
+    assert_eq!(migrated.virtual_debt, state.virtual_debt);
+
+    let report = verify_state(&mut ledger, new_cluster, &main_account);
+    assert!(report.valid, "Cluster is invalid: {:?}", report.violations);
+
+    //. Existing supply units remain valid with the new cluster
+    withdraw(
+        &mut ledger,
+        new_cluster,
+        &main_account,
+        admin_badge,
+        xrd_unit,
+        dec!(100),
+    )
+    .expect_commit_success();
+    supply(&mut ledger, new_cluster, &main_account, admin_badge, dec!(100)).expect_commit_success();
+
+    Ok(())
+}
+
+/// Tests that the first supply locks dead units and must meet the minimum initial supply
+#[test]
+fn initial_supply_test() -> Result<(), RuntimeError> {
+    // Deconstruct setup
+    let (mut ledger, _, (main_account, _), (_, admin_badge), (xrd_cluster, xrd_unit)) = setup();
+
+    //. A dust first supply is rejected
+    supply(
+        &mut ledger,
+        xrd_cluster,
+        &main_account,
+        admin_badge,
+        dec!(0.000000000000000001),
+    )
+    .expect_commit_failure();
+
+    //. The first supply locks dead units in the cluster
+    supply(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(1000)).expect_commit_success();
+
+    let units = ledger.get_component_balance(main_account.address, xrd_unit);
+    assert_eq!(units, dec!(999.999));
+
+    //. Once every lender has left, the dead units keep the ratio from being reset
+    withdraw(&mut ledger, xrd_cluster, &main_account, admin_badge, xrd_unit, units).expect_commit_success();
+
+    let state = get_cluster_state(&mut ledger, xrd_cluster, &main_account);
+    assert_eq!(state.supply_units, pdec!(0.001));
+    assert!(state.virtual_supply > pdec!(0), "Dead units lost their backing");
+
+    let report = verify_state(&mut ledger, xrd_cluster, &main_account);
+    assert!(report.valid, "Cluster is invalid: {:?}", report.violations);
+
+    Ok(())
+}
+
+/// Tests the first-depositor inflation attack: an attacker donates to the suppliers to inflate the value of a unit, so
+/// that a later supply truncates to zero units and is captured by the attacker
+#[test]
+fn inflation_attack_test() -> Result<(), RuntimeError> {
+    // Deconstruct setup
+    let (mut ledger, _, (main_account, user_account), (_, admin_badge), (xrd_cluster, _)) = setup();
+
+    //. The attacker makes the smallest allowed first supply
+    supply(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(1)).expect_commit_success();
+
+    //. Donate to the suppliers through flash loan fees, inflating the value of a unit
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(main_account.address, admin_badge, dec!(1))
+        .call_method(xrd_cluster, "set_flash_loan_fee", manifest_args!(pdec!(1)))
+        .build();
+    ledger
+        .execute_manifest(manifest, vec![main_account.nf_global_id()])
+        .expect_commit_success();
+
+    let receipt_resource = get_cluster_state(&mut ledger, xrd_cluster, &main_account).flash_loan_receipt;
+    for amount in [dec!(1), dec!(2)] {
+        let manifest = ManifestBuilder::new()
+            .lock_fee_from_faucet()
+            .call_method(xrd_cluster, "flash_borrow", manifest_args!(amount))
+            .withdraw_from_account(user_account.address, XRD, amount)
+            .take_from_worktop(XRD, amount * 2, "bucket_xrd")
+            .take_all_from_worktop(receipt_resource, "bucket_receipt")
+            .call_method_with_name_lookup(xrd_cluster, "flash_repay", |lookup| {
+                (lookup.bucket("bucket_xrd"), lookup.bucket("bucket_receipt"))
+            })
+            .deposit_batch(user_account.address)
+            .build();
+        ledger
+            .execute_manifest(manifest, vec![user_account.nf_global_id()])
+            .expect_commit_success();
+    }
+
+    let state = get_cluster_state(&mut ledger, xrd_cluster, &main_account);
+    assert!(
+        state.supply_ratio < pdec!(0.5),
+        "Unit value was not inflated: {}",
+        state.supply_ratio
+    );
+
+    //. A supply which would truncate to zero units is rejected rather than donated
+    supply(
+        &mut ledger,
+        xrd_cluster,
+        &main_account,
+        admin_badge,
+        dec!(0.000000000000000001),
+    )
+    .expect_commit_failure();
+
+    //. Regular supplies still receive their share
+    supply(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(10)).expect_commit_success();
+
+    let after = get_cluster_state(&mut ledger, xrd_cluster, &main_account);
+    assert!(
+        within(
+            after.supply_units - state.supply_units,
+            state.supply_ratio * pdec!(10),
+            pdec!(0.000001)
+        ),
+        "Supply was not minted at the current ratio"
+    );
+
+    Ok(())
+}
+
+/// Tests that conversions between amounts and units always round in the cluster's favor, over tiny and huge amounts
+#[test]
+fn rounding_test() -> Result<(), RuntimeError> {
+    const YEAR: i64 = 31_557_600;
+    const ATTO: Decimal = dec!(0.000000000000000001);
+
+    // Deconstruct setup
+    let (mut ledger, package_address, (main_account, _), (owner_badge, admin_badge), (xrd_cluster, _)) = setup();
+
+    supply(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(1000)).expect_commit_success();
+    borrow(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(500)).expect_commit_success();
+
+    //. Accrue interest, so that neither ratio is 1
+    ledger.advance_to_round_at_timestamp(Round::of(2), UNIX_2024 + YEAR);
+    tick_interest(&mut ledger, xrd_cluster, &main_account);
+
+    //. Directional conversions differ by the smallest representable amount when inexact
+    for layer in [ClusterLayer::Supply, ClusterLayer::Debt] {
+        for method in ["get_units", "get_amount"] {
+            let down = convert(
+                &mut ledger,
+                xrd_cluster,
+                &main_account,
+                method,
+                layer.clone(),
+                dec!(1),
+                Rounding::Down,
+            );
+            let up = convert(
+                &mut ledger,
+                xrd_cluster,
+                &main_account,
+                method,
+                layer.clone(),
+                dec!(1),
+                Rounding::Up,
+            );
+
+            assert_eq!(
+                up - down,
+                ATTO,
+                "{} on {:?} is not rounded directionally",
+                method,
+                layer
+            );
+        }
+    }
+
+    //. A tiny borrow still mints debt units, and a tiny supply which would mint no units is rejected
+    let before = get_cluster_state(&mut ledger, xrd_cluster, &main_account);
+    borrow(&mut ledger, xrd_cluster, &main_account, admin_badge, ATTO).expect_commit_success();
+    let after = get_cluster_state(&mut ledger, xrd_cluster, &main_account);
+
+    assert_eq!(after.debt_units - before.debt_units, PreciseDecimal::from(ATTO));
+
+    supply(&mut ledger, xrd_cluster, &main_account, admin_badge, ATTO).expect_commit_failure();
+
+    //. Round trips through a cluster of a huge resource never return more than was supplied
+    let huge = create_fungible(&mut ledger, dec!("3000000000000000000000000000"), "HUGE", &main_account);
+    let (huge_cluster, huge_unit) = instantiate(
+        &mut ledger,
+        package_address,
+        &main_account,
+        huge,
+        None,
+        rule!(require(owner_badge)),
+        rule!(require(admin_badge)),
+    );
+
+    let amount = dec!("1000000000000000000000000000");
+    supply_resource(&mut ledger, huge_cluster, &main_account, admin_badge, huge, amount).expect_commit_success();
+    borrow(&mut ledger, huge_cluster, &main_account, admin_badge, amount / dec!(2)).expect_commit_success();
+
+    ledger.advance_to_round_at_timestamp(Round::of(3), UNIX_2024 + YEAR * 2);
+
+    for amount in [amount, dec!(3) * ATTO] {
+        let units_before = ledger.get_component_balance(main_account.address, huge_unit);
+        supply_resource(&mut ledger, huge_cluster, &main_account, admin_badge, huge, amount).expect_commit_success();
+        let minted = ledger.get_component_balance(main_account.address, huge_unit) - units_before;
+
+        let huge_before = ledger.get_component_balance(main_account.address, huge);
+        withdraw(&mut ledger, huge_cluster, &main_account, admin_badge, huge_unit, minted).expect_commit_success();
+        let withdrawn = ledger.get_component_balance(main_account.address, huge) - huge_before;
+
+        assert!(withdrawn <= amount, "Round trip of {} returned {}", amount, withdrawn);
+    }
+
+    let report = verify_state(&mut ledger, huge_cluster, &main_account);
+    assert!(report.valid, "Cluster is invalid: {:?}", report.violations);
+
+    Ok(())
+}
+
+/// Tests that fixed-rate loans accrue at their locked rate, can be rebalanced once rates diverge, and can be repaid
+#[test]
+fn fixed_rate_test() -> Result<(), RuntimeError> {
+    const YEAR: i64 = 31_557_600;
+
+    // Deconstruct setup
+    let (mut ledger, _, (main_account, _), (_, admin_badge), (xrd_cluster, _)) = setup();
+
+    supply(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(1000)).expect_commit_success();
+
+    let before = get_cluster_state(&mut ledger, xrd_cluster, &main_account);
+    let loan_resource = before.fixed_rate_loan;
+
+    //. Borrow at a fixed rate
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(main_account.address, admin_badge, dec!(1))
+        .call_method(
+            xrd_cluster,
+            "borrow_fixed",
+            manifest_args!(dec!(100), None::<ManifestBucket>),
+        )
+        .deposit_batch(main_account.address)
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![main_account.nf_global_id()]);
+
+    log_tx("borrow_fixed", &receipt);
+    receipt.expect_commit_success();
+
+    let state = get_cluster_state(&mut ledger, xrd_cluster, &main_account);
+    let locked_rate = before.apr + before.fixed_rate_premium;
+
+    assert_eq!(state.fixed_loans, 1);
+    assert_eq!(state.fixed_debt, pdec!(100));
+    assert_eq!(state.fixed_rate, locked_rate, "Rate not locked at the offer");
+    assert!(
+        state.utilization > pdec!(0),
+        "Fixed-rate debt does not count towards utilization"
+    );
+
+    let vault = ledger.get_component_vaults(main_account.address, loan_resource)[0];
+    let (_, mut ids) = ledger.inspect_non_fungible_vault(vault).unwrap();
+    let loan_id = ids.next().unwrap();
+
+    //. Rebalancing fails while the rate is within the threshold of the offer
+    let rebalance = |ledger: &mut LedgerSimulator<NoExtension, InMemorySubstateDatabase>| {
+        let manifest = ManifestBuilder::new()
+            .lock_fee_from_faucet()
+            .create_proof_from_account_of_amount(main_account.address, admin_badge, dec!(1))
+            .call_method(xrd_cluster, "rebalance_fixed", manifest_args!(loan_id.clone()))
+            .build();
+        let receipt = ledger.execute_manifest(manifest, vec![main_account.nf_global_id()]);
+
+        log_tx("rebalance_fixed", &receipt);
+        receipt
+    };
+
+    rebalance(&mut ledger).expect_commit_failure();
+
+    //. After a year, the loan owes interest at its locked rate
+    ledger.advance_to_round_at_timestamp(Round::of(2), UNIX_2024 + YEAR);
+
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(xrd_cluster, "get_fixed_debt", manifest_args!(loan_id.clone()))
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![main_account.nf_global_id()]);
+
+    log_tx("get_fixed_debt", &receipt);
+    let owed: Decimal = receipt.expect_commit_success().output(1);
+
+    let expected = 100f64 * locked_rate.to_string().parse::<f64>().unwrap().exp();
+    assert!(
+        (owed.to_string().parse::<f64>().unwrap() - expected).abs() < 0.0001,
+        "Owed {} instead of {}",
+        owed,
+        expected
+    );
+
+    //. Link a model with a much higher borrow rate; the loan can now be rebalanced to the new offer
+    let model_package = ledger.compile_and_publish(format!("{}/tests/fixed-interest-model", this_package!()));
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_function(
+            model_package,
+            "FixedInterestModel",
+            "instantiate",
+            manifest_args!(pdec!(0.2), pdec!(0.1)),
+        )
+        .build();
+    let model = ledger
+        .execute_manifest(manifest, vec![])
+        .expect_commit(true)
+        .new_component_addresses()[0];
+
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(main_account.address, admin_badge, dec!(1))
+        .call_method(xrd_cluster, "link_interest_model", manifest_args!(model))
+        .build();
+    ledger
+        .execute_manifest(manifest, vec![main_account.nf_global_id()])
+        .expect_commit_success();
+
+    rebalance(&mut ledger).expect_commit_success();
+
+    let state = get_cluster_state(&mut ledger, xrd_cluster, &main_account);
+    assert!(
+        within(
+            state.fixed_rate,
+            pdec!(0.2) + state.fixed_rate_premium,
+            pdec!(0.000000001)
+        ),
+        "Loan not rebalanced to the offer"
+    );
+
+    //. Repay the loan in full; the excess repayment is returned and the loan is burnt
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(main_account.address, admin_badge, dec!(1))
+        .withdraw_non_fungibles_from_account(main_account.address, loan_resource, [loan_id])
+        .withdraw_from_account(main_account.address, XRD, dec!(200))
+        .take_all_from_worktop(XRD, "bucket_xrd")
+        .take_all_from_worktop(loan_resource, "bucket_loan")
+        .call_method_with_name_lookup(xrd_cluster, "repay_fixed", |lookup| {
+            (lookup.bucket("bucket_xrd"), lookup.bucket("bucket_loan"))
+        })
+        .deposit_batch(main_account.address)
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![main_account.nf_global_id()]);
+
+    log_tx("repay_fixed", &receipt);
+    receipt.expect_commit_success();
+
+    let state = get_cluster_state(&mut ledger, xrd_cluster, &main_account);
+    println!("State after repaying: {:#?}", state);
+
+    assert_eq!(state.fixed_loans, 0);
+    assert_eq!(state.fixed_debt, pdec!(0));
+    assert_eq!(state.virtual_fixed_debt, pdec!(0));
+    assert_eq!(
+        ledger.get_component_balance(main_account.address, loan_resource),
+        dec!(0)
+    );
+    assert!(state.supply > pdec!(1000), "Interest was not paid to the supply");
+
+    let report = verify_state(&mut ledger, xrd_cluster, &main_account);
+    assert!(report.valid, "Cluster is invalid: {:?}", report.violations);
+
+    Ok(())
+}
+
+/// Tests that suppliers earn exactly the interest paid on fixed-rate debt, less the reserve factor
+#[test]
+fn fixed_rate_accrual_test() -> Result<(), RuntimeError> {
+    const YEAR: i64 = 31_557_600;
+
+    // Deconstruct setup
+    let (mut ledger, _, (main_account, _), (_, admin_badge), (xrd_cluster, _)) = setup();
+
+    supply(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(1000)).expect_commit_success();
+
+    //. Borrow only at a fixed rate
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(main_account.address, admin_badge, dec!(1))
+        .call_method(
+            xrd_cluster,
+            "borrow_fixed",
+            manifest_args!(dec!(500), None::<ManifestBucket>),
+        )
+        .deposit_batch(main_account.address)
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![main_account.nf_global_id()]);
+
+    log_tx("borrow_fixed", &receipt);
+    receipt.expect_commit_success();
+
+    let before = get_cluster_state(&mut ledger, xrd_cluster, &main_account);
+
+    //. After a year, the interest paid is split between suppliers and reserves
+    ledger.advance_to_round_at_timestamp(Round::of(2), UNIX_2024 + YEAR);
+    tick_interest(&mut ledger, xrd_cluster, &main_account);
+
+    let after = get_cluster_state(&mut ledger, xrd_cluster, &main_account);
+
+    let interest_paid = after.virtual_fixed_debt - before.virtual_fixed_debt;
+    let supplier_interest = after.virtual_supply - before.virtual_supply;
+    let reserves_interest = after.reserves - before.reserves;
+
+    assert!(interest_paid > pdec!(0), "Fixed-rate debt did not accrue interest");
+    assert!(
+        within(
+            supplier_interest,
+            interest_paid * (pdec!(1) - after.reserve_factor),
+            pdec!(0.000001)
+        ),
+        "Suppliers earned {} of {} interest paid",
+        supplier_interest,
+        interest_paid
+    );
+    assert!(
+        within(supplier_interest + reserves_interest, interest_paid, pdec!(0.000001)),
+        "Accrued interest does not match the interest paid"
+    );
+
+    let report = verify_state(&mut ledger, xrd_cluster, &main_account);
+    assert!(report.valid, "Cluster is invalid: {:?}", report.violations);
+
+    Ok(())
+}
+
+/// Tests that repaying fixed-rate loans at different rates credits suppliers and reserves with exactly the interest
+/// paid, even though the aggregate fixed-rate debt compounds at their average rate
+#[test]
+fn fixed_rate_drift_test() -> Result<(), RuntimeError> {
+    const YEAR: i64 = 31_557_600;
+
+    // Deconstruct setup
+    let (mut ledger, _, (main_account, _), (_, admin_badge), (xrd_cluster, _)) = setup();
+
+    supply(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(1000)).expect_commit_success();
+
+    let before = get_cluster_state(&mut ledger, xrd_cluster, &main_account);
+    let loan_resource = before.fixed_rate_loan;
+
+    let borrow_fixed = |ledger: &mut LedgerSimulator<NoExtension, InMemorySubstateDatabase>, amount: Decimal| {
+        let manifest = ManifestBuilder::new()
+            .lock_fee_from_faucet()
+            .create_proof_from_account_of_amount(main_account.address, admin_badge, dec!(1))
+            .call_method(
+                xrd_cluster,
+                "borrow_fixed",
+                manifest_args!(amount, None::<ManifestBucket>),
+            )
+            .deposit_batch(main_account.address)
+            .build();
+        let receipt = ledger.execute_manifest(manifest, vec![main_account.nf_global_id()]);
+
+        log_tx("borrow_fixed", &receipt);
+        receipt.expect_commit_success();
+    };
+
+    //. Borrow at the default offer, then at a much higher one
+    borrow_fixed(&mut ledger, dec!(100));
+
+    let model_package = ledger.compile_and_publish(format!("{}/tests/fixed-interest-model", this_package!()));
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_function(
+            model_package,
+            "FixedInterestModel",
+            "instantiate",
+            manifest_args!(pdec!(0.5), pdec!(0.1)),
+        )
+        .build();
+    let model = ledger
+        .execute_manifest(manifest, vec![])
+        .expect_commit(true)
+        .new_component_addresses()[0];
+
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(main_account.address, admin_badge, dec!(1))
+        .call_method(xrd_cluster, "link_interest_model", manifest_args!(model))
+        .build();
+    ledger
+        .execute_manifest(manifest, vec![main_account.nf_global_id()])
+        .expect_commit_success();
+
+    borrow_fixed(&mut ledger, dec!(400));
+
+    //. After a year, repay both loans in full
+    ledger.advance_to_round_at_timestamp(Round::of(2), UNIX_2024 + YEAR);
+
+    let balance_before = ledger.get_component_balance(main_account.address, XRD);
+
+    let vault = ledger.get_component_vaults(main_account.address, loan_resource)[0];
+    let (_, ids) = ledger.inspect_non_fungible_vault(vault).unwrap();
+    for loan_id in ids.collect::<Vec<_>>() {
+        let manifest = ManifestBuilder::new()
+            .lock_fee_from_faucet()
+            .create_proof_from_account_of_amount(main_account.address, admin_badge, dec!(1))
+            .withdraw_non_fungibles_from_account(main_account.address, loan_resource, [loan_id])
+            .withdraw_from_account(main_account.address, XRD, dec!(1000))
+            .take_all_from_worktop(XRD, "bucket_xrd")
+            .take_all_from_worktop(loan_resource, "bucket_loan")
+            .call_method_with_name_lookup(xrd_cluster, "repay_fixed", |lookup| {
+                (lookup.bucket("bucket_xrd"), lookup.bucket("bucket_loan"))
+            })
+            .deposit_batch(main_account.address)
+            .build();
+        let receipt = ledger.execute_manifest(manifest, vec![main_account.nf_global_id()]);
+
+        log_tx("repay_fixed", &receipt);
+        receipt.expect_commit_success();
+    }
+
+    let repaid = balance_before - ledger.get_component_balance(main_account.address, XRD);
+    let interest_paid = PreciseDecimal::from(repaid) - pdec!(500);
+
+    let after = get_cluster_state(&mut ledger, xrd_cluster, &main_account);
+    let interest_earned = (after.virtual_supply - before.virtual_supply) + (after.reserves - before.reserves);
+
+    assert_eq!(after.fixed_loans, 0);
+    assert_eq!(after.fixed_debt_units, pdec!(0));
+    assert_eq!(after.virtual_fixed_debt, pdec!(0));
+    assert!(
+        within(interest_earned, interest_paid, pdec!(0.000001)),
+        "Suppliers and reserves earned {} of {} interest paid",
+        interest_earned,
+        interest_paid
+    );
+
+    let report = verify_state(&mut ledger, xrd_cluster, &main_account);
+    assert!(report.valid, "Cluster is invalid: {:?}", report.violations);
+
+    Ok(())
+}
+
+/// Tests that a fixed-rate loan can be written off as bad debt
+#[test]
+fn absorb_bad_fixed_debt_test() -> Result<(), RuntimeError> {
+    // Deconstruct setup
+    let (mut ledger, _, (main_account, _), (_, admin_badge), (xrd_cluster, _)) = setup();
+
+    supply(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(1000)).expect_commit_success();
+
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(main_account.address, admin_badge, dec!(1))
+        .call_method(
+            xrd_cluster,
+            "borrow_fixed",
+            manifest_args!(dec!(500), None::<ManifestBucket>),
+        )
+        .deposit_batch(main_account.address)
+        .build();
+    ledger
+        .execute_manifest(manifest, vec![main_account.nf_global_id()])
+        .expect_commit_success();
+
+    let loan_resource = get_cluster_state(&mut ledger, xrd_cluster, &main_account).fixed_rate_loan;
+
+    //. Write off the loan
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(main_account.address, admin_badge, dec!(1))
+        .withdraw_from_account(main_account.address, loan_resource, dec!(1))
+        .take_all_from_worktop(loan_resource, "bucket_loan")
+        .call_method_with_name_lookup(xrd_cluster, "absorb_bad_fixed_debt", |lookup| {
+            (lookup.bucket("bucket_loan"),)
+        })
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![main_account.nf_global_id()]);
+
+    log_tx("absorb_bad_fixed_debt", &receipt);
+    receipt.expect_commit_success();
+
+    let state = get_cluster_state(&mut ledger, xrd_cluster, &main_account);
+    assert_eq!(state.fixed_debt, pdec!(0));
+    assert_eq!(state.virtual_fixed_debt, pdec!(0));
+    assert_eq!(state.fixed_loans, 0);
+    assert_eq!(state.supply, pdec!(500));
+    assert!(state.supply_ratio > pdec!(1), "Loss was not socialized across lenders");
+
+    let report = verify_state(&mut ledger, xrd_cluster, &main_account);
+    assert!(report.valid, "Cluster is invalid: {:?}", report.violations);
+
+    Ok(())
+}
+
+/// Tests that withdrawals beyond the cluster's liquidity are queued, filled by new supply, and can be claimed or
+/// cancelled
+#[test]
+fn withdrawal_queue_test() -> Result<(), RuntimeError> {
+    // Deconstruct setup
+    let (mut ledger, _, (main_account, _), (_, admin_badge), (xrd_cluster, xrd_unit)) = setup();
+
+    supply(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(1000)).expect_commit_success();
+    borrow(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(900)).expect_commit_success();
+
+    let units = ledger.get_component_balance(main_account.address, xrd_unit);
+    let claim_resource = get_cluster_state(&mut ledger, xrd_cluster, &main_account).withdrawal_claim;
+
+    //. Withdrawing beyond the liquidity fails outright
+    withdraw(&mut ledger, xrd_cluster, &main_account, admin_badge, xrd_unit, units).expect_commit_failure();
+
+    //. Queue the withdrawal instead; it is filled with the available liquidity right away
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(main_account.address, admin_badge, dec!(1))
+        .withdraw_from_account(main_account.address, xrd_unit, units)
+        .take_from_worktop(xrd_unit, units, "bucket_units")
+        .call_method_with_name_lookup(xrd_cluster, "request_withdrawal", |lookup| {
+            (lookup.bucket("bucket_units"),)
+        })
+        .deposit_batch(main_account.address)
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![main_account.nf_global_id()]);
+
+    log_tx("request_withdrawal", &receipt);
+    receipt.expect_commit_success();
+
+    let state = get_cluster_state(&mut ledger, xrd_cluster, &main_account);
+    println!("State after queueing: {:#?}", state);
+
+    assert_eq!(state.withdrawal_claims, 1);
+    assert!(state.queued_units > dec!(0), "No units left queued");
+    assert!(
+        within(state.claimable.into(), pdec!(100), pdec!(0.000001)),
+        "Claim not filled from the available liquidity"
+    );
+
+    let vault = ledger.get_component_vaults(main_account.address, claim_resource)[0];
+    let (_, mut ids) = ledger.inspect_non_fungible_vault(vault).unwrap();
+    let claim_id = ids.next().unwrap();
+
+    //. New supply goes to the queued withdrawal first
+    supply(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(500)).expect_commit_success();
+
+    let state = get_cluster_state(&mut ledger, xrd_cluster, &main_account);
+    assert!(
+        within(state.claimable.into(), pdec!(600), pdec!(0.000001)),
+        "Claim not filled from new supply"
+    );
+
+    //. Redeem what has been filled; the claim stays queued for the rest
+    let xrd_before = ledger.get_component_balance(main_account.address, XRD);
+
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .withdraw_non_fungibles_from_account(main_account.address, claim_resource, [claim_id.clone()])
+        .take_all_from_worktop(claim_resource, "bucket_claim")
+        .call_method_with_name_lookup(xrd_cluster, "claim_withdrawal", |lookup| {
+            (lookup.bucket("bucket_claim"),)
+        })
+        .deposit_batch(main_account.address)
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![main_account.nf_global_id()]);
+
+    log_tx("claim_withdrawal", &receipt);
+    receipt.expect_commit_success();
+
+    let claimed = ledger.get_component_balance(main_account.address, XRD) - xrd_before;
+    assert!(
+        within(claimed.into(), pdec!(600), pdec!(0.01)),
+        "Claimed {} instead of 600",
+        claimed
+    );
+    assert_eq!(
+        ledger.get_component_balance(main_account.address, claim_resource),
+        dec!(1)
+    );
+
+    //. Cancel the claim; only the admin can cancel, and the remaining units are returned
+    let queued = get_cluster_state(&mut ledger, xrd_cluster, &main_account).queued_units;
+    let units_before = ledger.get_component_balance(main_account.address, xrd_unit);
+
+    let cancel_withdrawal = |ledger: &mut LedgerSimulator<NoExtension, InMemorySubstateDatabase>, with_badge: bool| {
+        let mut builder = ManifestBuilder::new().lock_fee_from_faucet();
+        if with_badge {
+            builder = builder.create_proof_from_account_of_amount(main_account.address, admin_badge, dec!(1));
+        }
+        let manifest = builder
+            .withdraw_non_fungibles_from_account(main_account.address, claim_resource, [claim_id.clone()])
+            .take_all_from_worktop(claim_resource, "bucket_claim")
+            .call_method_with_name_lookup(xrd_cluster, "cancel_withdrawal", |lookup| {
+                (lookup.bucket("bucket_claim"),)
+            })
+            .deposit_batch(main_account.address)
+            .build();
+        let receipt = ledger.execute_manifest(manifest, vec![main_account.nf_global_id()]);
+
+        log_tx("cancel_withdrawal", &receipt);
+        receipt
+    };
+
+    cancel_withdrawal(&mut ledger, false).expect_commit_failure();
+    cancel_withdrawal(&mut ledger, true).expect_commit_success();
+
+    assert_eq!(
+        ledger.get_component_balance(main_account.address, xrd_unit) - units_before,
+        queued
+    );
+
+    let state = get_cluster_state(&mut ledger, xrd_cluster, &main_account);
+    assert_eq!(state.withdrawal_claims, 0);
+    assert_eq!(state.queued_units, dec!(0));
+
+    let report = verify_state(&mut ledger, xrd_cluster, &main_account);
+    assert!(report.valid, "Cluster is invalid: {:?}", report.violations);
+
+    Ok(())
+}
+
+/// Tests that cluster operations emit their events
+#[test]
+fn events_test() -> Result<(), RuntimeError> {
+    // Deconstruct setup
+    let (mut ledger, _, (main_account, _), (_, admin_badge), (xrd_cluster, _)) = setup();
+
+    let receipt = supply(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(1000));
+    let events = receipt.expect_commit_success().application_events.clone();
+
+    let supply_event: ClusterSupplyEvent = events
+        .iter()
+        .find(|(id, _)| ledger.is_event_name_equal::<ClusterSupplyEvent>(id))
+        .map(|(_, data)| scrypto_decode(data).unwrap())
+        .expect("No ClusterSupplyEvent emitted");
+
+    assert_eq!(supply_event.amount, dec!(1000));
+    assert_eq!(supply_event.units, dec!(1000));
+
+    //. Interest is ticked before the supply
+    let tick_event: InterestTickEvent = events
+        .iter()
+        .find(|(id, _)| ledger.is_event_name_equal::<InterestTickEvent>(id))
+        .map(|(_, data)| scrypto_decode(data).unwrap())
+        .expect("No InterestTickEvent emitted");
+
+    assert_eq!(tick_event.old_virtual_supply, pdec!(0));
+    assert_eq!(tick_event.new_virtual_supply, pdec!(0));
+
+    Ok(())
+}
+
+/// Tests that keepers are rewarded out of reserves for due, non-forced interest ticks only
+#[test]
+fn keeper_reward_test() -> Result<(), RuntimeError> {
+    // Deconstruct setup
+    let (mut ledger, _, (main_account, user_account), (_, admin_badge), (xrd_cluster, _)) = setup();
+
+    supply(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(1000)).expect_commit_success();
+    borrow(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(500)).expect_commit_success();
+
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(main_account.address, admin_badge, dec!(1))
+        .call_method(xrd_cluster, "set_keeper_reward", manifest_args!(dec!(1)))
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![main_account.nf_global_id()]);
+
+    log_tx("set_keeper_reward", &receipt);
+    receipt.expect_commit_success();
+
+    let keeper_tick = |ledger: &mut LedgerSimulator<NoExtension, InMemorySubstateDatabase>, force: bool| {
+        let manifest = ManifestBuilder::new()
+            .lock_fee_from_faucet()
+            .call_method(xrd_cluster, "tick_interest", manifest_args!(force))
+            .deposit_batch(user_account.address)
+            .build();
+        let receipt = ledger.execute_manifest(manifest, vec![user_account.nf_global_id()]);
+
+        log_tx("tick_interest", &receipt);
+        receipt.expect_commit_success();
+    };
+
+    //. Let a year of interest accrue to the reserves
+    let start = now(&mut ledger);
+    ledger.advance_to_round_at_timestamp(Round::of(2), start + 31_557_600);
+
+    //. A forced tick accrues interest without paying a reward
+    let xrd_before = ledger.get_component_balance(user_account.address, XRD);
+    keeper_tick(&mut ledger, true);
+    assert_eq!(ledger.get_component_balance(user_account.address, XRD), xrd_before);
+
+    let reserves_before = get_cluster_state(&mut ledger, xrd_cluster, &main_account).reserves;
+    assert!(reserves_before > pdec!(1), "Reserves did not accrue");
+
+    //. A non-forced tick before the interval elapses does nothing
+    keeper_tick(&mut ledger, false);
+    assert_eq!(ledger.get_component_balance(user_account.address, XRD), xrd_before);
+
+    //. A due non-forced tick pays the keeper no more than the reserves it accrued
+    let state = get_cluster_state(&mut ledger, xrd_cluster, &main_account);
+    ledger.advance_to_round_at_timestamp(Round::of(3), now(&mut ledger) + state.interest_tick_interval + 1);
+
+    keeper_tick(&mut ledger, false);
+    let reward = ledger.get_component_balance(user_account.address, XRD) - xrd_before;
+    assert!(
+        reward > dec!(0) && reward < dec!(1),
+        "Keeper was paid {} for a short tick",
+        reward
+    );
+
+    let state = get_cluster_state(&mut ledger, xrd_cluster, &main_account);
+    assert!(
+        within(state.reserves, reserves_before, pdec!(0.00000000000000001)),
+        "Reward was not capped at the accrued reserves"
+    );
+
+    //. After another year, the tick accrues more than the reward, so the keeper is paid all of it
+    let xrd_before = ledger.get_component_balance(user_account.address, XRD);
+    ledger.advance_to_round_at_timestamp(Round::of(4), now(&mut ledger) + 31_557_600);
+
+    keeper_tick(&mut ledger, false);
+    assert_eq!(
+        ledger.get_component_balance(user_account.address, XRD),
+        xrd_before + dec!(1),
+        "Keeper was not rewarded"
+    );
+
+    let report = verify_state(&mut ledger, xrd_cluster, &main_account);
+    assert!(report.valid, "Cluster is invalid: {:?}", report.violations);
+
+    Ok(())
+}
+
+/// Tests that a cluster is instantiated with the given configuration, and that invalid configurations are rejected
+#[test]
+fn cluster_config_test() -> Result<(), RuntimeError> {
+    // Deconstruct setup
+    let (mut ledger, package_address, (main_account, _), (owner_badge, admin_badge), _) = setup();
+
+    let usd = create_fungible(&mut ledger, dec!(10000), "USD", &main_account);
+
+    //. Instantiate with a custom configuration
+    let config = ClusterConfig {
+        reserve_factor: pdec!(0.2),
+        supply_cap: Some(dec!(100)),
+        interest_tick_interval: 60,
+        unit_name: Some("Lattic3 Dollar".to_string()),
+        unit_symbol: Some("ltUSD".to_string()),
+        unit_icon_url: Some("https://lattic3.com/icons/ltUSD.png".to_string()),
+        status: ClusterStatus::Frozen,
+        ..ClusterConfig::default()
+    };
+
+    let (usd_cluster, usd_unit) = instantiate(
+        &mut ledger,
+        package_address,
+        &main_account,
+        usd,
+        Some(config),
+        rule!(require(owner_badge)),
+        rule!(require(admin_badge)),
+    );
+
+    let state = get_cluster_state(&mut ledger, usd_cluster, &main_account);
+    assert_eq!(state.reserve_factor, pdec!(0.2));
+    assert_eq!(state.supply_cap, Some(dec!(100)));
+    assert_eq!(state.interest_tick_interval, 60);
+    assert_eq!(state.status, ClusterStatus::Frozen);
+
+    assert_eq!(
+        ledger.get_metadata(usd_unit.into(), "name"),
+        Some(MetadataValue::String("Lattic3 Dollar".to_string()))
+    );
+    assert_eq!(
+        ledger.get_metadata(usd_unit.into(), "symbol"),
+        Some(MetadataValue::String("ltUSD".to_string()))
+    );
+    assert!(
+        ledger.get_metadata(usd_unit.into(), "icon_url").is_some(),
+        "Icon not set"
+    );
+
+    //. The cluster starts frozen, and honours its supply cap once active
+    supply_resource(&mut ledger, usd_cluster, &main_account, admin_badge, usd, dec!(50)).expect_commit_failure();
+
+    set_status(
+        &mut ledger,
+        usd_cluster,
+        &main_account,
+        admin_badge,
+        ClusterStatus::Active,
+    );
+    supply_resource(&mut ledger, usd_cluster, &main_account, admin_badge, usd, dec!(50)).expect_commit_success();
+    supply_resource(&mut ledger, usd_cluster, &main_account, admin_badge, usd, dec!(60)).expect_commit_failure();
+
+    //. Invalid configurations are rejected
+    for config in [
+        ClusterConfig { reserve_factor: pdec!(1), ..ClusterConfig::default() },
+        ClusterConfig { interest_tick_interval: 0, ..ClusterConfig::default() },
+        ClusterConfig { unit_symbol: Some(String::new()), ..ClusterConfig::default() },
+    ] {
+        let manifest = ManifestBuilder::new()
+            .lock_fee_from_faucet()
+            .call_function(
+                package_address,
+                "Cluster",
+                "instantiate",
+                manifest_args!(
+                    usd,
+                    Some(config),
+                    rule!(require(owner_badge)),
+                    rule!(require(admin_badge))
+                ),
+            )
+            .build();
+        let receipt = ledger.execute_manifest(manifest, vec![main_account.nf_global_id()]);
+
+        log_tx("instantiate", &receipt);
+        receipt.expect_commit_failure();
+    }
+
+    Ok(())
+}
+
+/// Tests that previewed amounts include pending interest without ticking it, and match the amounts after a tick
+#[test]
+fn preview_amount_test() -> Result<(), RuntimeError> {
+    const YEAR: i64 = 31_557_600;
+
+    // Deconstruct setup
+    let (mut ledger, _, (main_account, _), (_, admin_badge), (xrd_cluster, _)) = setup();
+
+    supply(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(1000)).expect_commit_success();
+    borrow(&mut ledger, xrd_cluster, &main_account, admin_badge, dec!(500)).expect_commit_success();
+
+    //. Let a year of interest build up without ticking
+    ledger.advance_to_round_at_timestamp(Round::of(2), UNIX_2024 + YEAR);
+    let ticked = get_cluster_state(&mut ledger, xrd_cluster, &main_account).apr_ticked;
+
+    let mut previews = Vec::new();
+    for (layer, units, rounding) in [
+        (ClusterLayer::Supply, dec!(1000), Rounding::Down),
+        (ClusterLayer::Debt, dec!(500), Rounding::Up),
+    ] {
+        let stale = convert(
+            &mut ledger,
+            xrd_cluster,
+            &main_account,
+            "get_amount",
+            layer.clone(),
+            units,
+            rounding,
+        );
+        let preview = convert(
+            &mut ledger,
+            xrd_cluster,
+            &main_account,
+            "preview_amount",
+            layer.clone(),
+            units,
+            rounding,
+        );
+
+        assert!(
+            preview > stale,
+            "Preview {} does not include pending interest over {}",
+            preview,
+            stale
+        );
+        previews.push((layer, units, rounding, preview));
+    }
+
+    //. Previews do not tick the cluster
+    let state = get_cluster_state(&mut ledger, xrd_cluster, &main_account);
+    assert_eq!(state.apr_ticked, ticked, "Preview ticked the cluster");
+
+    //. Once ticked, the amounts match the previews
+    tick_interest(&mut ledger, xrd_cluster, &main_account);
+
+    for (layer, units, rounding, preview) in previews {
+        let amount = convert(
+            &mut ledger,
+            xrd_cluster,
+            &main_account,
+            "get_amount",
+            layer,
+            units,
+            rounding,
+        );
+        assert!(
+            within(amount.into(), preview.into(), pdec!(0.000001)),
+            "Amount {} after the tick differs from preview {}",
+            amount,
+            preview
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,85 @@
+/* ------------------ Imports ----------------- */
+use scrypto::prelude::*;
+
+/* -------------- Interest Curve -------------- */
+/// Parameters for a kinked, utilization-based interest rate curve; shared by the cluster's built-in curve and the
+/// `InterestModel` blueprint.
+///
+/// Below `optimal_utilization` the borrow rate grows linearly by `slope`; above it, the rate grows by `jump_slope`
+/// to discourage the cluster's liquidity from being drained.
+#[derive(ScryptoSbor, ManifestSbor, Debug, Clone)]
+pub struct InterestRateModel {
+    pub base_rate: PreciseDecimal,           // Borrow rate at 0% utilization
+    pub slope: PreciseDecimal,               // Borrow rate increase between 0% and optimal utilization
+    pub jump_slope: PreciseDecimal,          // Borrow rate increase between optimal and 100% utilization
+    pub optimal_utilization: PreciseDecimal, // Utilization at which the curve kinks
+}
+
+impl Default for InterestRateModel {
+    /// Returns the default interest rate curve: 2% base, +10% up to 80% utilization, +100% above it.
+    fn default() -> Self {
+        InterestRateModel {
+            base_rate: pdec!(0.02),
+            slope: pdec!(0.1),
+            jump_slope: pdec!(1),
+            optimal_utilization: pdec!(0.8),
+        }
+    }
+}
+
+impl InterestRateModel {
+    /// Asserts that the model parameters are within legal bounds.
+    ///
+    /// # Panics
+    /// * If any rate or slope is negative.
+    /// * If the optimal utilization is not within (0, 1).
+    pub fn validate(&self) {
+        assert!(self.base_rate >= pdec!(0), "Base rate must be non-negative");
+        assert!(self.slope >= pdec!(0), "Slope must be non-negative");
+        assert!(self.jump_slope >= pdec!(0), "Jump slope must be non-negative");
+        assert!(
+            self.optimal_utilization > pdec!(0) && self.optimal_utilization < pdec!(1),
+            "Optimal utilization must be between 0 and 1 (exclusive)"
+        );
+    }
+
+    /// Calculates the borrow and supply APRs for the given utilization.
+    ///
+    /// The supply APR is the borrow APR scaled by utilization, since only the borrowed share of the supply earns interest.
+    ///
+    /// # Returns
+    /// * The borrow APR.
+    /// * The supply APR.
+    pub fn get_rates(&self, utilization: PreciseDecimal) -> (PreciseDecimal, PreciseDecimal) {
+        let borrow_rate = if utilization <= self.optimal_utilization {
+            let scaled = utilization.checked_div(self.optimal_utilization).unwrap();
+            self.base_rate
+                .checked_add(scaled.checked_mul(self.slope).unwrap())
+                .unwrap()
+        } else {
+            let excess = utilization
+                .checked_sub(self.optimal_utilization)
+                .unwrap()
+                .checked_div(pdec!(1).checked_sub(self.optimal_utilization).unwrap())
+                .unwrap();
+            self.base_rate
+                .checked_add(self.slope)
+                .unwrap()
+                .checked_add(excess.checked_mul(self.jump_slope).unwrap())
+                .unwrap()
+        };
+
+        let supply_rate = borrow_rate.checked_mul(utilization).unwrap();
+
+        (borrow_rate, supply_rate)
+    }
+}
+
+/// Returns the share of the supply that is currently borrowed, bounded to [0, 1].
+pub fn utilization(supply: PreciseDecimal, debt: PreciseDecimal) -> PreciseDecimal {
+    if supply <= pdec!(0) {
+        return pdec!(0);
+    }
+
+    debt.checked_div(supply).unwrap().min(pdec!(1))
+}
@@ -0,0 +1,103 @@
+/* ------------------ Imports ----------------- */
+use lattic3_interest_curve::{utilization, InterestRateModel};
+use scrypto::prelude::*;
+
+/* -------------- Interest Model -------------- */
+// Default interest strategy for Lattic3 clusters; a kinked, utilization-based curve.
+//
+// Any component exposing `get_rates(supply, debt, liquidity, elapsed) -> (borrow_rate, supply_rate)` can be linked to a
+// cluster in place of this blueprint.
+#[blueprint]
+mod lattic3_interest_model {
+    enable_method_auth! {
+        methods {
+            get_rates      => PUBLIC;
+            set_parameters => restrict_to: [OWNER];
+        }
+    }
+
+    struct InterestModel {
+        model: InterestRateModel, // Kinked curve shared with the cluster's built-in model
+    }
+
+    impl InterestModel {
+        /// Instantiates a new kinked `InterestModel` component.
+        ///
+        /// # Parameters
+        /// * `owner_rule`: An `AccessRule` that defines the owner of the model, who may update its parameters.
+        /// * `base_rate`, `slope`, `jump_slope`, `optimal_utilization`: The curve parameters (see `set_parameters`).
+        ///
+        /// # Returns
+        /// * A `Global<InterestModel>` instance representing the newly created model component.
+        pub fn instantiate(
+            owner_rule: AccessRule,
+            base_rate: PreciseDecimal,
+            slope: PreciseDecimal,
+            jump_slope: PreciseDecimal,
+            optimal_utilization: PreciseDecimal,
+        ) -> Global<InterestModel> {
+            let model = InterestRateModel { base_rate, slope, jump_slope, optimal_utilization };
+            model.validate();
+
+            let component_metadata = metadata! {
+                roles {
+                    metadata_setter         => OWNER;
+                    metadata_setter_updater => OWNER;
+                    metadata_locker         => OWNER;
+                    metadata_locker_updater => rule!(deny_all);
+                },
+                init {
+                    "name"        => "Lattic3 Interest Model", locked;
+                    "description" => "Kinked, utilization-based interest rate model for Lattic3 clusters", locked;
+                }
+            };
+
+            InterestModel { model }
+                .instantiate()
+                .prepare_to_globalize(OwnerRole::Fixed(owner_rule))
+                .metadata(component_metadata)
+                .globalize()
+        }
+
+        /// Calculates the borrow and supply APRs of a cluster.
+        ///
+        /// The supply APR is the borrow APR scaled by utilization, since only the borrowed share of the supply earns interest.
+        ///
+        /// # Parameters
+        /// * `supply` - The cluster's raw supply.
+        /// * `debt` - The cluster's raw debt.
+        /// * `liquidity` - The cluster's available liquidity (unused by this model).
+        /// * `elapsed` - Seconds since the cluster last ticked interest (unused by this model).
+        ///
+        /// # Returns
+        /// * The borrow APR.
+        /// * The supply APR.
+        pub fn get_rates(
+            &self,
+            supply: PreciseDecimal,
+            debt: PreciseDecimal,
+            _liquidity: Decimal,
+            _elapsed: i64,
+        ) -> (PreciseDecimal, PreciseDecimal) {
+            self.model.get_rates(utilization(supply, debt))
+        }
+
+        /// Replaces the curve parameters.
+        ///
+        /// # Panics
+        /// * If any rate or slope is negative.
+        /// * If the optimal utilization is not within (0, 1).
+        pub fn set_parameters(
+            &mut self,
+            base_rate: PreciseDecimal,
+            slope: PreciseDecimal,
+            jump_slope: PreciseDecimal,
+            optimal_utilization: PreciseDecimal,
+        ) {
+            let model = InterestRateModel { base_rate, slope, jump_slope, optimal_utilization };
+            model.validate();
+
+            self.model = model;
+        }
+    }
+}
@@ -0,0 +1,215 @@
+/* ------------------ Imports ----------------- */
+use scrypto_test::prelude::*;
+
+/* ---------------- Test Setup ---------------- */
+// Test config
+const LOG_TX: bool = true;
+
+// Struct to hold account data
+struct Account {
+    public_key: Secp256k1PublicKey,
+    address: ComponentAddress,
+}
+
+impl Account {
+    pub fn new(ledger: &mut LedgerSimulator<NoExtension, InMemorySubstateDatabase>) -> Self {
+        let (public_key, _, address) = ledger.new_allocated_account();
+        Account { public_key, address }
+    }
+
+    pub fn nf_global_id(&self) -> NonFungibleGlobalId {
+        NonFungibleGlobalId::from_public_key(self.public_key)
+    }
+}
+
+/// Log transaction with name [func]
+fn log_tx(func: &str, tx: &TransactionReceiptV1) {
+    if LOG_TX {
+        println!(
+            "[{}] Transaction Receipt:\n{}\n",
+            func,
+            tx.display(&AddressBech32Encoder::for_simulator())
+        );
+    }
+}
+
+/// Curve parameters: base rate, slope, jump slope, optimal utilization
+type Parameters = (PreciseDecimal, PreciseDecimal, PreciseDecimal, PreciseDecimal);
+
+/// The default cluster curve: 2% base, +10% up to 80% utilization, +100% above it
+const DEFAULT_PARAMETERS: Parameters = (pdec!(0.02), pdec!(0.1), pdec!(1), pdec!(0.8));
+
+/* ------------- Helper Manifests ------------- */
+/// Initialise a ledger with the model package published, and an owner account holding the owner badge
+fn setup() -> (
+    LedgerSimulator<NoExtension, InMemorySubstateDatabase>, // Ledger simulation
+    PackageAddress,                                         // Package
+    Account,                                                // Owner account
+    ResourceAddress,                                        // Owner badge
+) {
+    let mut ledger = LedgerSimulatorBuilder::new().build();
+    let owner = Account::new(&mut ledger);
+    let owner_badge = ledger.create_fungible_resource(dec!(1), DIVISIBILITY_NONE, owner.address);
+
+    let package_address = ledger.compile_and_publish(this_package!());
+
+    (ledger, package_address, owner, owner_badge)
+}
+
+/// Instantiates an `InterestModel` owned by `owner_badge`
+fn instantiate(
+    ledger: &mut LedgerSimulator<NoExtension, InMemorySubstateDatabase>,
+    package_address: PackageAddress,
+    owner_badge: ResourceAddress,
+    parameters: Parameters,
+) -> TransactionReceiptV1 {
+    let (base_rate, slope, jump_slope, optimal_utilization) = parameters;
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_function(
+            package_address,
+            "InterestModel",
+            "instantiate",
+            manifest_args!(
+                rule!(require(owner_badge)),
+                base_rate,
+                slope,
+                jump_slope,
+                optimal_utilization
+            ),
+        )
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![]);
+
+    log_tx("instantiate", &receipt);
+    receipt
+}
+
+/// Gets the borrow and supply APRs of the model for a cluster's raw supply and debt
+fn get_rates(
+    ledger: &mut LedgerSimulator<NoExtension, InMemorySubstateDatabase>,
+    model: ComponentAddress,
+    supply: PreciseDecimal,
+    debt: PreciseDecimal,
+) -> (PreciseDecimal, PreciseDecimal) {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(model, "get_rates", manifest_args!(supply, debt, dec!(0), 0i64))
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![]);
+
+    log_tx("get_rates", &receipt);
+    receipt.expect_commit_success().output(1)
+}
+
+/// Replaces the model's parameters, proving the owner badge if `with_badge`
+fn set_parameters(
+    ledger: &mut LedgerSimulator<NoExtension, InMemorySubstateDatabase>,
+    model: ComponentAddress,
+    (owner, owner_badge): (&Account, ResourceAddress),
+    with_badge: bool,
+    parameters: Parameters,
+) -> TransactionReceiptV1 {
+    let (base_rate, slope, jump_slope, optimal_utilization) = parameters;
+    let mut builder = ManifestBuilder::new().lock_fee_from_faucet();
+    if with_badge {
+        builder = builder.create_proof_from_account_of_amount(owner.address, owner_badge, dec!(1));
+    }
+    let manifest = builder
+        .call_method(
+            model,
+            "set_parameters",
+            manifest_args!(base_rate, slope, jump_slope, optimal_utilization),
+        )
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![owner.nf_global_id()]);
+
+    log_tx("set_parameters", &receipt);
+    receipt
+}
+
+/* ----------------- Curve Tests ---------------- */
+/// Tests the borrow and supply APRs below, at, and above the kink of the curve
+#[test]
+fn get_rates_test() -> Result<(), RuntimeError> {
+    let (mut ledger, package_address, _, owner_badge) = setup();
+    let receipt = instantiate(&mut ledger, package_address, owner_badge, DEFAULT_PARAMETERS);
+    let model = receipt.expect_commit_success().new_component_addresses()[0];
+
+    //. Without supply, utilization is 0; the borrow APR is the base rate, and suppliers earn nothing
+    assert_eq!(
+        get_rates(&mut ledger, model, pdec!(0), pdec!(0)),
+        (pdec!(0.02), pdec!(0))
+    );
+    assert_eq!(
+        get_rates(&mut ledger, model, pdec!(100), pdec!(0)),
+        (pdec!(0.02), pdec!(0))
+    );
+
+    //. Below the kink, the borrow APR grows by the slope: 2% + 40% / 80% * 10%
+    assert_eq!(
+        get_rates(&mut ledger, model, pdec!(100), pdec!(40)),
+        (pdec!(0.07), pdec!(0.028))
+    );
+
+    //. At the kink, the borrow APR is the base rate plus the full slope
+    assert_eq!(
+        get_rates(&mut ledger, model, pdec!(100), pdec!(80)),
+        (pdec!(0.12), pdec!(0.096))
+    );
+
+    //. Above the kink, the borrow APR grows by the jump slope: 12% + 10% / 20% * 100%
+    assert_eq!(
+        get_rates(&mut ledger, model, pdec!(100), pdec!(90)),
+        (pdec!(0.62), pdec!(0.558))
+    );
+
+    //. Utilization is bounded to 100%, even if the debt exceeds the supply
+    assert_eq!(
+        get_rates(&mut ledger, model, pdec!(100), pdec!(100)),
+        (pdec!(1.12), pdec!(1.12))
+    );
+    assert_eq!(
+        get_rates(&mut ledger, model, pdec!(100), pdec!(150)),
+        (pdec!(1.12), pdec!(1.12))
+    );
+
+    Ok(())
+}
+
+/// Tests that invalid parameters are rejected, and that only the owner can replace the parameters
+#[test]
+fn parameters_test() -> Result<(), RuntimeError> {
+    let (mut ledger, package_address, owner, owner_badge) = setup();
+
+    //. Negative rates and slopes, and an optimal utilization outside (0, 1), are rejected
+    let invalid = [
+        (pdec!(-0.01), pdec!(0.1), pdec!(1), pdec!(0.8)),
+        (pdec!(0.02), pdec!(-0.1), pdec!(1), pdec!(0.8)),
+        (pdec!(0.02), pdec!(0.1), pdec!(-1), pdec!(0.8)),
+        (pdec!(0.02), pdec!(0.1), pdec!(1), pdec!(0)),
+        (pdec!(0.02), pdec!(0.1), pdec!(1), pdec!(1)),
+    ];
+    for parameters in invalid {
+        instantiate(&mut ledger, package_address, owner_badge, parameters).expect_commit_failure();
+    }
+
+    let receipt = instantiate(&mut ledger, package_address, owner_badge, DEFAULT_PARAMETERS);
+    let model = receipt.expect_commit_success().new_component_addresses()[0];
+
+    for parameters in invalid {
+        set_parameters(&mut ledger, model, (&owner, owner_badge), true, parameters).expect_commit_failure();
+    }
+
+    //. Only the owner can replace the parameters
+    let flat = (pdec!(0.05), pdec!(0), pdec!(0), pdec!(0.5));
+    set_parameters(&mut ledger, model, (&owner, owner_badge), false, flat).expect_commit_failure();
+    set_parameters(&mut ledger, model, (&owner, owner_badge), true, flat).expect_commit_success();
+
+    assert_eq!(
+        get_rates(&mut ledger, model, pdec!(100), pdec!(90)),
+        (pdec!(0.05), pdec!(0.045))
+    );
+
+    Ok(())
+}
@@ -1,5 +1,6 @@
 /* ------------------ Imports ----------------- */
 use crate::cluster::ClusterWrapper;
+use crate::utils::{DEFAULT_LIQUIDATION_BONUS, DEFAULT_LIQUIDATION_THRESHOLD, DEFAULT_LTV};
 use scrypto::prelude::*;
 
 /* --------------- Asset Struct --------------- */
@@ -13,6 +14,10 @@ pub struct AssetEntry {
     pub symbol: String,
 
     pub cluster_wrapper: ClusterWrapper,
+
+    pub ltv: Decimal,                   // Share of the asset's supplied value which can be borrowed against
+    pub liquidation_threshold: Decimal, // Share of the asset's supplied value counted towards a position's liquidation health
+    pub liquidation_bonus: Decimal,     // Share of the repaid value paid to liquidators on top, when seizing this asset
 }
 
 impl AssetEntry {
@@ -22,13 +27,73 @@ impl AssetEntry {
         assert!(resource_manager.resource_type().is_fungible(), "Provided asset must be fungible.");
 
         // Setup resource
-        let name: String = resource_manager.get_metadata("name").expect("Cannot get asset name").expect("Asset name is None");
-        let symbol: String = resource_manager.get_metadata("symbol").expect("Cannot get asset symbol").expect("Asset symbol is None");
+        let name: String = resource_manager
+            .get_metadata("name")
+            .expect("Cannot get asset name")
+            .expect("Asset name is None");
+        let symbol: String = resource_manager
+            .get_metadata("symbol")
+            .expect("Cannot get asset symbol")
+            .expect("Asset symbol is None");
         // let description: String = resource_manager.get_metadata("description").expect("Cannot get asset description").expect("Asset description is None");
 
         // Setup cluster
-        assert!(cluster_wrapper.resource == address, "Asset address does not match cluster's asset address");
+        assert!(
+            cluster_wrapper.resource == address,
+            "Asset address does not match cluster's asset address"
+        );
+
+        AssetEntry {
+            address,
+            resource_manager,
+            name,
+            symbol,
+            cluster_wrapper,
+            ltv: DEFAULT_LTV,
+            liquidation_threshold: DEFAULT_LIQUIDATION_THRESHOLD,
+            liquidation_bonus: DEFAULT_LIQUIDATION_BONUS,
+        }
+    }
+
+    /* ----------------- Collateral ---------------- */
+    /// Sets the collateral parameters of the asset.
+    ///
+    /// # Panics
+    /// * If the LTV is not within [0, liquidation threshold].
+    /// * If the liquidation threshold is not within [LTV, 1).
+    /// * If the liquidation bonus is not within [0, 1).
+    /// * If seizing the asset with the bonus would lower a position's liquidation health (threshold * (1 + bonus) > 1).
+    pub fn set_collateral_parameters(&mut self, ltv: Decimal, liquidation_threshold: Decimal, liquidation_bonus: Decimal) {
+        assert!(
+            ltv >= dec!(0.0) && ltv <= liquidation_threshold,
+            "LTV must be between 0 and the liquidation threshold (inclusive)"
+        );
+        assert!(liquidation_threshold < dec!(1.0), "Liquidation threshold must be below 1");
+        assert!(
+            liquidation_bonus >= dec!(0.0) && liquidation_bonus < dec!(1.0),
+            "Liquidation bonus must be between 0 (inclusive) and 1 (exclusive)"
+        );
+        assert!(
+            liquidation_threshold
+                .checked_mul(dec!(1.0).checked_add(liquidation_bonus).unwrap())
+                .unwrap()
+                <= dec!(1.0),
+            "Liquidation threshold and bonus must not exceed 1 combined (threshold * (1 + bonus))"
+        );
+
+        self.ltv = ltv;
+        self.liquidation_threshold = liquidation_threshold;
+        self.liquidation_bonus = liquidation_bonus;
+    }
+
+    /// Caps a liquidation bonus paid in this asset, so seizing it cannot lower a position's liquidation health
+    /// (threshold * (1 + bonus) <= 1).
+    pub fn cap_liquidation_bonus(&self, bonus: Decimal) -> Decimal {
+        if self.liquidation_threshold == dec!(0.0) {
+            return bonus;
+        }
 
-        AssetEntry { address, resource_manager, name, symbol, cluster_wrapper }
+        let max_bonus = dec!(1.0).checked_div(self.liquidation_threshold).unwrap().checked_sub(dec!(1.0)).unwrap();
+        bonus.min(max_bonus)
     }
 }
@@ -8,8 +8,19 @@ pub struct ClusterState {
     pub at: i64, // seconds
 
     pub resource: ResourceAddress,
+    pub authority: ResourceAddress,
     pub supply_unit: ResourceAddress,
+    pub debt_unit: ResourceAddress,
+    pub flash_loan_receipt: ResourceAddress,
+    pub fixed_rate_loan: ResourceAddress,
+    pub withdrawal_claim: ResourceAddress,
     pub liquidity: Decimal,
+    pub flash_loaned: Decimal,
+    pub provided_liquidity: PreciseDecimal,
+
+    pub queued_units: Decimal,
+    pub claimable: Decimal,
+    pub withdrawal_claims: u64,
 
     pub supply: PreciseDecimal,
     pub supply_units: PreciseDecimal,
@@ -21,8 +32,33 @@ pub struct ClusterState {
     pub virtual_debt: PreciseDecimal,
     pub debt_ratio: PreciseDecimal,
 
+    pub fixed_debt: PreciseDecimal,
+    pub fixed_debt_units: PreciseDecimal,
+    pub virtual_fixed_debt: PreciseDecimal,
+    pub fixed_rate: PreciseDecimal,
+    pub fixed_loans: u64,
+    pub fixed_rate_premium: PreciseDecimal,
+    pub fixed_rebalance_threshold: PreciseDecimal,
+
+    pub utilization: PreciseDecimal,
     pub apr: PreciseDecimal,
+    pub supply_apr: PreciseDecimal,
     pub apr_ticked: i64, // seconds
+
+    pub interest_model: InterestRateModel,
+    pub interest_model_address: Option<ComponentAddress>,
+    pub interest_tick_interval: i64, // seconds
+    pub keeper_reward: Decimal,
+
+    pub reserves: PreciseDecimal,
+    pub reserve_factor: PreciseDecimal,
+    pub flash_loan_fee: PreciseDecimal,
+
+    pub supply_cap: Option<Decimal>,
+    pub borrow_cap: Option<Decimal>,
+
+    pub status: ClusterStatus,
+    pub operations: ClusterOperations,
 }
 
 #[derive(ScryptoSbor, Debug, Clone)]
@@ -31,12 +67,65 @@ pub enum ClusterLayer {
     Debt,
 }
 
+/// Direction in which a cluster rounds a conversion between amounts and units; it must always favor the cluster.
+#[derive(ScryptoSbor, Debug, Clone, Copy, PartialEq)]
+pub enum Rounding {
+    Down,
+    Up,
+}
+
+#[derive(ScryptoSbor, Debug, Clone, PartialEq)]
+pub enum ClusterStatus {
+    Active,
+    Frozen,
+    Paused,
+}
+
+#[derive(ScryptoSbor, Debug, Clone)]
+pub struct ClusterOperations {
+    pub supply: bool,
+    pub withdraw: bool,
+    pub borrow: bool,
+    pub repay: bool,
+}
+
+#[derive(ScryptoSbor, Debug, Clone)]
+pub struct InterestRateModel {
+    pub base_rate: PreciseDecimal,
+    pub slope: PreciseDecimal,
+    pub jump_slope: PreciseDecimal,
+    pub optimal_utilization: PreciseDecimal,
+}
+
+#[derive(ScryptoSbor, Debug, Clone)]
+pub struct ClusterConfig {
+    pub interest_model: InterestRateModel,
+    pub reserve_factor: PreciseDecimal,
+    pub flash_loan_fee: PreciseDecimal,
+    pub fixed_rate_premium: PreciseDecimal,
+    pub fixed_rebalance_threshold: PreciseDecimal,
+
+    pub supply_cap: Option<Decimal>,
+    pub borrow_cap: Option<Decimal>,
+
+    pub interest_tick_interval: i64, // seconds
+    pub keeper_reward: Decimal,
+
+    pub unit_name: Option<String>,
+    pub unit_symbol: Option<String>,
+    pub unit_icon_url: Option<String>,
+
+    pub status: ClusterStatus,
+}
+
 #[derive(ScryptoSbor, Debug, Clone)]
 pub struct ClusterWrapper {
     pub cluster: Global<Cluster>,
 
     pub resource: ResourceAddress,
     pub supply_unit: ResourceAddress,
+    pub debt_unit: ResourceAddress,
+    pub withdrawal_claim: ResourceAddress,
 }
 
 impl ClusterWrapper {
@@ -48,17 +137,26 @@ impl ClusterWrapper {
             cluster,
             resource: cluster_state.resource,
             supply_unit: cluster_state.supply_unit,
+            debt_unit: cluster_state.debt_unit,
+            withdrawal_claim: cluster_state.withdrawal_claim,
         }
     }
 
-    pub fn create(resource: ResourceAddress, cluster_owner: AccessRule, cluster_manager: AccessRule) -> ClusterWrapper {
-        let cluster = Blueprint::<Cluster>::instantiate(resource, cluster_owner, cluster_manager);
+    pub fn create(
+        resource: ResourceAddress,
+        config: Option<ClusterConfig>,
+        cluster_owner: AccessRule,
+        cluster_manager: AccessRule,
+    ) -> ClusterWrapper {
+        let cluster = Blueprint::<Cluster>::instantiate(resource, config, cluster_owner, cluster_manager);
         let cluster_state = cluster.get_cluster_state();
 
         ClusterWrapper {
             cluster,
             resource: cluster_state.resource,
             supply_unit: cluster_state.supply_unit,
+            debt_unit: cluster_state.debt_unit,
+            withdrawal_claim: cluster_state.withdrawal_claim,
         }
     }
 
@@ -1,4 +1,5 @@
 /* ------------------ Imports ----------------- */
+use crate::liquidation::AuctionConfig;
 use crate::utils::{ValueMap, ValueTuple};
 use scrypto::prelude::*;
 
@@ -52,6 +53,35 @@ pub struct PositionRepayEvent {
 #[derive(ScryptoSbor, ScryptoEvent)]
 pub struct PositionCloseEvent {}
 
+//. Liquidation
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct LiquidationEvent {
+    pub position_id: NonFungibleLocalId,
+    pub health: Decimal, // Health of the position before liquidation
+
+    pub debt_asset: ResourceAddress,
+    pub repaid: Decimal,
+
+    pub collateral_asset: ResourceAddress,
+    pub seized: Decimal,
+    pub seized_units: Decimal,
+    pub bonus: Decimal, // Share of the repaid value paid on top, in seized collateral
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct AuctionStartEvent {
+    pub position_id: NonFungibleLocalId,
+    pub health: Decimal,
+
+    pub config: AuctionConfig,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct AuctionEndEvent {
+    pub position_id: NonFungibleLocalId,
+    pub health: Decimal, // Health of the position as the auction ends
+}
+
 //. Internal position operations
 #[derive(ScryptoSbor, ScryptoEvent)]
 pub struct PositionHealthEvent {
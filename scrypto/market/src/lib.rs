@@ -1,6 +1,7 @@
 mod asset;
 mod cluster;
 mod events;
+mod liquidation;
 mod market;
 mod position;
 mod utils;
@@ -0,0 +1,79 @@
+/* ------------------ Imports ----------------- */
+use crate::utils::{DEFAULT_AUCTION_DURATION, DEFAULT_AUCTION_MAX_BONUS, DEFAULT_AUCTION_START_BONUS};
+use scrypto::prelude::*;
+
+/* ------------------- Modes ------------------ */
+/// Strategy used by the market to price the collateral seized by liquidations.
+#[derive(ScryptoSbor, Debug, Clone, Copy, PartialEq)]
+pub enum LiquidationMode {
+    /// Collateral is seized at the collateral asset's fixed `liquidation_bonus`, up to the close factor of the debt.
+    FixedBonus,
+    /// Unhealthy positions are put up for auction; the bonus grows over time, and the auction can be filled in full.
+    Auction,
+}
+
+/* ------------------ Auction ----------------- */
+/// Parameters of liquidation auctions; copied into each auction as it starts.
+#[derive(ScryptoSbor, Debug, Clone, Copy, PartialEq)]
+pub struct AuctionConfig {
+    pub start_bonus: Decimal, // Bonus offered as the auction starts
+    pub max_bonus: Decimal,   // Bonus offered once `duration` has elapsed
+    pub duration: i64,        // Seconds for the bonus to grow from `start_bonus` to `max_bonus`
+}
+
+impl Default for AuctionConfig {
+    fn default() -> Self {
+        AuctionConfig {
+            start_bonus: DEFAULT_AUCTION_START_BONUS,
+            max_bonus: DEFAULT_AUCTION_MAX_BONUS,
+            duration: DEFAULT_AUCTION_DURATION,
+        }
+    }
+}
+
+impl AuctionConfig {
+    /// Validates the auction parameters.
+    ///
+    /// The bonus paid by an auction is further capped per collateral asset, as it must not lower a position's
+    /// liquidation health; see `AssetEntry::cap_liquidation_bonus`.
+    ///
+    /// # Panics
+    /// * If the start bonus is not within [0, max bonus].
+    /// * If the max bonus is not below 1.
+    /// * If the duration is not positive.
+    pub fn validate(&self) {
+        assert!(
+            self.start_bonus >= dec!(0.0) && self.start_bonus <= self.max_bonus,
+            "Auction start bonus must be between 0 and the max bonus (inclusive)"
+        );
+        assert!(self.max_bonus < dec!(1.0), "Auction max bonus must be below 1");
+        assert!(self.duration > 0, "Auction duration must be positive");
+    }
+}
+
+/// A running liquidation auction of an unhealthy position.
+#[derive(ScryptoSbor, Debug, Clone)]
+pub struct LiquidationAuction {
+    pub started_at: i64, // Seconds since the Unix epoch
+    pub config: AuctionConfig,
+}
+
+impl LiquidationAuction {
+    pub fn new(started_at: i64, config: AuctionConfig) -> Self {
+        LiquidationAuction { started_at, config }
+    }
+
+    /// Bonus offered at `now`; grows linearly from the start to the max bonus over the auction's duration.
+    pub fn bonus(&self, now: i64) -> Decimal {
+        let elapsed = Decimal::from((now - self.started_at).clamp(0, self.config.duration));
+        let progress = elapsed.checked_div(Decimal::from(self.config.duration)).unwrap();
+        let growth = self.config.max_bonus.checked_sub(self.config.start_bonus).unwrap();
+
+        self.config.start_bonus.checked_add(growth.checked_mul(progress).unwrap()).unwrap()
+    }
+
+    /// Whether the auction has run past its duration at `now`; expired auctions cannot be filled, and can be restarted.
+    pub fn is_expired(&self, now: i64) -> bool {
+        now - self.started_at > self.config.duration
+    }
+}
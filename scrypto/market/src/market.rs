@@ -1,10 +1,11 @@
 /* ------------------ Imports ----------------- */
 // Usages
 use crate::asset::AssetEntry;
-use crate::cluster::{ClusterLayer, ClusterState, ClusterWrapper};
+use crate::cluster::{ClusterConfig, ClusterLayer, ClusterState, ClusterWrapper, Rounding};
 use crate::events::*;
-use crate::position::Position;
-use crate::utils::ValueMap;
+use crate::liquidation::{AuctionConfig, LiquidationAuction, LiquidationMode};
+use crate::position::{BorrowMode, Position, PositionAction, PositionPreview};
+use crate::utils::{add_to_value_map, now, ValueMap, DEFAULT_CLOSE_FACTOR, ZERO_PRICE};
 use scrypto::prelude::*;
 
 /* ----------------- Blueprint ---------------- */
@@ -18,6 +19,10 @@ use scrypto::prelude::*;
     PositionWithdrawEvent,
     PositionRepayEvent,
     PositionCloseEvent,
+    // Liquidation
+    LiquidationEvent,
+    AuctionStartEvent,
+    AuctionEndEvent,
     // Internal position operations
     PositionHealthEvent,
     // Asset management
@@ -26,7 +31,16 @@ use scrypto::prelude::*;
     UntrackAssetEvent
 )]
 // Types registered to reduce fees; include those used for KV stores, structs, NFTs, etc.
-#[types(Decimal, ResourceAddress, ValueMap, ComponentAddress, GlobalAddress, AssetEntry, Position)]
+#[types(
+    Decimal,
+    ResourceAddress,
+    ValueMap,
+    ComponentAddress,
+    GlobalAddress,
+    AssetEntry,
+    Position,
+    LiquidationAuction
+)]
 mod lattic3 {
     //] --------------- Scrypto Setup -------------- /
 
@@ -42,13 +56,31 @@ mod lattic3 {
             position_borrow   => PUBLIC;
             position_withdraw => PUBLIC;
             position_repay    => PUBLIC;
+            position_cancel_withdrawal => PUBLIC;
+            position_execute  => PUBLIC;
             // Internal position operations
             get_position_health       => PUBLIC;
             calculate_health_from_units => PUBLIC;
+            // Previews
+            preview_supply   => PUBLIC;
+            preview_borrow   => PUBLIC;
+            preview_withdraw => PUBLIC;
+            preview_repay    => PUBLIC;
+            // Liquidation
+            liquidate_position    => PUBLIC;
+            start_auction         => PUBLIC;
+            end_auction           => PUBLIC;
+            set_close_factor      => restrict_to: [SELF, OWNER, admin];
+            set_liquidation_mode  => restrict_to: [SELF, OWNER, admin];
+            set_auction_config    => restrict_to: [SELF, OWNER, admin];
+            set_collateral_parameters => restrict_to: [SELF, OWNER, admin];
+            // Loss management
+            absorb_bad_debt => restrict_to: [SELF, OWNER, admin];
             // Asset management
             add_asset     => restrict_to: [SELF, OWNER];
             track_asset   => restrict_to: [SELF, OWNER, admin];
             untrack_asset => restrict_to: [SELF, OWNER, admin];
+            migrate_cluster => restrict_to: [OWNER];
             // Price stream management
             link_price_stream   => restrict_to: [SELF, OWNER];
             unlink_price_stream => restrict_to: [SELF, OWNER];
@@ -69,20 +101,28 @@ mod lattic3 {
         // "package_sim1pkys4qlttszxq29qw5ys9lvn8grmswd0n6nsxrdxce3er3l85eagjm", // Resim
         "package_tdx_2_1pkxjgnf0sc8cyv63fucee0eqmqjewnrnwqwnt4vwckwgpjwj844t8e", // Stokenet
         Cluster {
-            fn instantiate(resource: ResourceAddress, cluster_owner_rule: AccessRule, cluster_admin_rule: AccessRule) -> Global<Cluster>;
+            fn instantiate(resource: ResourceAddress, config: Option<ClusterConfig>, cluster_owner_rule: AccessRule, cluster_admin_rule: AccessRule) -> Global<Cluster>;
 
             fn supply(&mut self, supply: Bucket) -> Bucket;
             fn withdraw(&mut self, units: Bucket) -> Bucket;
-            fn borrow(&mut self, amount: Decimal) -> (Bucket, Decimal);
-            fn repay(&mut self, repayment: Bucket) -> Decimal;
+            fn request_withdrawal(&mut self, units: Bucket) -> NonFungibleBucket;
+            fn cancel_withdrawal(&mut self, claim: NonFungibleBucket) -> (Bucket, Bucket);
+            fn borrow(&mut self, amount: Decimal) -> (Bucket, Bucket);
+            fn repay(&mut self, repayment: Bucket, units: Bucket) -> Bucket;
+            fn absorb_bad_debt(&mut self, units: Bucket);
+            fn absorb_bad_fixed_debt(&mut self, loan: NonFungibleBucket);
+            fn borrow_fixed(&mut self, amount: Decimal, loan: Option<NonFungibleBucket>) -> (Bucket, NonFungibleBucket);
+            fn repay_fixed(&mut self, repayment: Bucket, loan: NonFungibleBucket) -> (Bucket, Option<NonFungibleBucket>);
 
             fn get_ratio(&self, layer: ClusterLayer) -> PreciseDecimal;
-            fn get_units(&self, layer: ClusterLayer, amount: Decimal) -> Decimal;
-            fn get_amount(&self, layer: ClusterLayer, unit_amount: Decimal) -> Decimal;
+            fn get_units(&self, layer: ClusterLayer, amount: Decimal, rounding: Rounding) -> Decimal;
+            fn get_amount(&self, layer: ClusterLayer, unit_amount: Decimal, rounding: Rounding) -> Decimal;
+            fn preview_amount(&self, layer: ClusterLayer, unit_amount: Decimal, rounding: Rounding) -> Decimal;
+            fn get_fixed_debt(&self, loan_id: NonFungibleLocalId) -> Decimal;
             fn get_cluster_state(&self) -> ClusterState;
 
             fn provide_liquidity(&mut self, provided: Bucket);
-            fn tick_interest(&mut self, force: bool);
+            fn tick_interest(&mut self, force: bool) -> Option<Bucket>;
             fn set_interest_tick_interval(&mut self, interval: i64);
         }
     }
@@ -99,11 +139,20 @@ mod lattic3 {
         assets: KeyValueStore<ResourceAddress, AssetEntry>,
         address_to_supply_unit: KeyValueStore<ResourceAddress, ResourceAddress>,
         supply_unit_to_address: KeyValueStore<ResourceAddress, ResourceAddress>,
+        withdrawal_claim_to_address: KeyValueStore<ResourceAddress, ResourceAddress>,
 
         price_stream_address: Option<ComponentAddress>,
 
         position_manager: ResourceManager,
         position_id: u64,
+        position_supply: KeyValueStore<(NonFungibleLocalId, ResourceAddress), Vault>, // Supply units held in custody per position
+        position_debt: KeyValueStore<(NonFungibleLocalId, ResourceAddress), Vault>,   // Debt units held in custody per position
+        position_fixed_debt: KeyValueStore<(NonFungibleLocalId, ResourceAddress), NonFungibleVault>, // Fixed-rate loans held in custody per position
+
+        close_factor: Decimal, // Maximum share of a position's debt in an asset repaid by a single liquidation
+        liquidation_mode: LiquidationMode,
+        auction_config: AuctionConfig,                                   // Parameters of auctions started from now on
+        auctions: KeyValueStore<NonFungibleLocalId, LiquidationAuction>, // Running liquidation auctions per position
     }
 
     impl Lattic3 {
@@ -190,9 +239,17 @@ mod lattic3 {
                 assets: KeyValueStore::new(),
                 address_to_supply_unit: KeyValueStore::new(),
                 supply_unit_to_address: KeyValueStore::new(),
+                withdrawal_claim_to_address: KeyValueStore::new(),
                 price_stream_address: None,
                 position_manager,
                 position_id: 0u64,
+                position_supply: KeyValueStore::new(),
+                position_debt: KeyValueStore::new(),
+                position_fixed_debt: KeyValueStore::new(),
+                close_factor: DEFAULT_CLOSE_FACTOR,
+                liquidation_mode: LiquidationMode::FixedBonus,
+                auction_config: AuctionConfig::default(),
+                auctions: KeyValueStore::new(),
             };
 
             //] Component
@@ -239,48 +296,34 @@ mod lattic3 {
         /// Opens a new position with the provided supply.
         ///
         /// Distributes supplied resources to corresponding clusters in exchange for supply units,
-        /// and mints a new position badge.
+        /// which are held in custody for the position, and mints a new position badge.
         ///
         /// # Arguments
         /// * `supply` - A vector of `Bucket`s which is the supply for the new position.
         ///
         /// # Returns
         /// * A `Bucket` of the minted position badge.
-        /// * A vector of `Bucket`s, which are the supply units corresponding to the input supply.
         ///
         /// # Panics
         /// * If the `supply` vector is empty.
         /// * If ome supplied resource is invalid (see `__validate_bucket`).
+        /// * If some supply would exceed its cluster's supply cap.
         /// * If the maximum number of positions has been reached.
-        pub fn open_position(&mut self, supply: Vec<Bucket>) -> (Bucket, Vec<Bucket>) {
+        pub fn open_position(&mut self, supply: Vec<Bucket>) -> Bucket {
             // Sanity checks
             assert!(self.__validate_buckets(&supply), "Invalid supply");
             assert!(self.position_id != u64::MAX, "Cannot open more positions");
 
             // Initialise empty position
+            self.position_id += 1;
+            let local_id = NonFungibleLocalId::Integer(self.position_id.into());
             let mut position = Position::new();
 
             // Supply resources to clusters
-            let mut supply_units: Vec<Bucket> = Vec::new();
-            let mut unit_map: ValueMap = HashMap::new();
-
-            for bucket in supply {
-                let address = bucket.resource_address();
-                let mut asset = self.assets.get_mut(&address).expect("Cannot get asset entry");
-
-                let pool_unit = asset.cluster_wrapper.cluster.supply(bucket);
-
-                unit_map.insert(address, pool_unit.amount());
-                supply_units.push(pool_unit);
-            }
-
-            position.update_supply(&unit_map);
+            self.__position_supply(&mut position, &local_id, supply);
 
             // Mint and return position NFT
-            self.position_id += 1;
-            let position_badge = self
-                .position_manager
-                .mint_non_fungible(&NonFungibleLocalId::Integer(self.position_id.into()), position);
+            let position_badge = self.position_manager.mint_non_fungible(&local_id, position);
 
             // Fire open position event
             // Runtime::emit_event(OpenPositionEvent {
@@ -290,7 +333,7 @@ mod lattic3 {
             info!("[open_position] Position event");
 
             // Return
-            (position_badge, supply_units)
+            position_badge
         }
 
         /// Closes a position by burning the position NFT.
@@ -309,6 +352,7 @@ mod lattic3 {
             // Fetch NFT data
             let position: Position = position_bucket.as_non_fungible().non_fungible::<Position>().data();
             assert!(position.debt.is_empty(), "Cannot close position with debts");
+            assert!(position.fixed_debt.is_empty(), "Cannot close position with fixed-rate debts");
             assert!(position.supply.is_empty(), "Cannot close position with supplied assets");
 
             // Burn the nft
@@ -323,23 +367,21 @@ mod lattic3 {
         /// Supplies resources to a position.
         ///
         /// Distributes supplied resources to corresponding clusters in exchange for supply units,
-        /// and updates the position NFT accordingly.
+        /// which are held in custody for the position, and updates the position NFT accordingly.
         ///
         /// # Arguments
         /// * `position_node` - A proof of the position NFT.
         /// * `supply` - A vector of `Bucket`s which are the supplied assets.
         ///
-        /// # Returns
-        /// * A vector of `Bucket`s of returned the supply units.
-        ///
         /// # Panics
         /// * If the `position` is invalid (see `__validate_position`).
         /// * If the `supply` vector is empty.
         /// * If some supplied resource is invalid (see `__validate_bucket`).
+        /// * If some supply would exceed its cluster's supply cap.
         ///
         /// # Events
         /// * Emits a `PositionSupplyEvent` on successful supply.
-        pub fn position_supply(&mut self, position_node: NonFungibleProof, supply: Vec<Bucket>) -> Vec<Bucket> {
+        pub fn position_supply(&mut self, position_node: NonFungibleProof, supply: Vec<Bucket>) {
             // Sanity checks
             assert!(self.__validate_buckets(&supply), "Invalid supply");
 
@@ -349,82 +391,51 @@ mod lattic3 {
 
             // Supply resources to clusters
             // let supply_map = self.__buckets_to_value_map(&supply);
-            let mut supply_units: Vec<Bucket> = Vec::new();
-            let mut unit_map: ValueMap = HashMap::new();
-
-            for bucket in supply {
-                let address = bucket.resource_address();
-                let mut asset = self.assets.get_mut(&address).expect("Cannot get asset entry");
-
-                let pool_unit = asset.cluster_wrapper.cluster.supply(bucket);
-
-                unit_map.insert(address, pool_unit.amount());
-                supply_units.push(pool_unit);
-            }
-
-            position.update_supply(&unit_map);
+            self.__position_supply(&mut position, &local_id, supply);
 
             // Update NFT data
             self.position_manager.update_non_fungible_data(&local_id, "supply", position.supply);
 
             // Fire position supply event
             // Runtime::emit_event(PositionSupplyEvent { position_id: local_id, supply: supply_map, supply_units: unit_map });
-
-            // Return
-            supply_units
         }
 
         /// Withdraws assets from a position's supply.
         ///
         /// # Arguments
         /// * `position_node` - A proof of the position NFT.
-        /// * `units` - A `Bucket` of the supply units to withdraw.
+        /// * `address` - The `ResourceAddress` of the supplied asset to withdraw.
         /// * `requested` - An optional `Decimal` representing the maximum amount of assets to withdraw.
-        ///                 If not provided, the full amount will be withdrawn.
-        ///                 Otherwise, the unredeemed supply units are returned as change.
+        ///                 If not provided, the position's full supply of the asset will be withdrawn.
+        ///
+        /// If the cluster's liquidity cannot cover the withdrawal, or other withdrawals are already queued, the withdrawal is
+        /// queued instead; the returned claim is redeemed through the cluster's `claim_withdrawal` as liquidity returns.
         ///
         /// # Returns
-        /// * A `Bucket` of leftover supply units if limited by `requested`.
-        /// * A `Bucket` of the withdrawn assets.
+        /// * A `Bucket` of the withdrawn assets; empty if the withdrawal was queued.
+        /// * A `NonFungibleBucket` of the withdrawal claim, if the withdrawal was queued.
         ///
         /// # Panics
         /// * If the `position_node` is invalid (see `__validate_position`).
-        /// * If the `units` bucket is empty.
-        /// * If the operation puts the position in an invalid state (health below 1.0).
+        /// * If the position does not supply the asset.
+        /// * If the operation puts the position in an invalid state (borrow health below 1.0).
         ///
         /// # Events
         /// * Emits a `PositionWithdrawEvent` on successful withdrawal
-        pub fn position_withdraw(&mut self, position_node: NonFungibleProof, mut units: Bucket, requested: Option<Decimal>) -> (Bucket, Bucket) {
+        pub fn position_withdraw(
+            &mut self,
+            position_node: NonFungibleProof,
+            address: ResourceAddress,
+            requested: Option<Decimal>,
+        ) -> (Bucket, Option<NonFungibleBucket>) {
             // Sanity checks
             let (mut position, local_id) = self.__validate_position(position_node);
             info!("[position_supply] Position: {:#?}", position);
 
-            let unit_address = units.resource_address();
-            assert!(!units.is_empty(), "Bucket for {:?} is empty", unit_address);
-
-            // Get debt unit's source asset
-            let address = *self
-                .supply_unit_to_address
-                .get(&unit_address)
-                .expect(format!("Cannot get address for pool unit {:?}", unit_address).as_str());
-            let mut cluster = self.assets.get(&address).expect("Cannot get asset entry").cluster_wrapper.cluster;
-
-            // If requested is Some, limit the amount of units provided by the unit amount of requested
-            let requested_units: Decimal = if let Some(amount) = requested {
-                cluster.get_units(ClusterLayer::Supply, amount)
-            } else {
-                Decimal::MAX
-            };
-            let unit_amount: Decimal = units.amount().min(requested_units);
-
-            // Recalculate supply
-            position.update_supply(&HashMap::from([(address, unit_amount.checked_mul(dec!(-1)).unwrap())]));
+            let (withdrawn, claim) = self.__position_withdraw(&mut position, &local_id, address, requested);
 
-            // Withdraw from cluster
-            let withdrawn = cluster.withdraw(units.take(unit_amount));
-
-            // Ensure that operation won't put position health below 1.0
-            let health = self.calculate_health_from_units(position.supply.clone(), position.debt.clone());
+            // Ensure that operation won't put the position's debt above its borrowing capacity
+            let (health, _) = self.__position_health(&position);
             assert!(health >= dec!(1.0), "Position health will be below 1.0. Reverting operation");
 
             // Update NFT data
@@ -437,7 +448,46 @@ mod lattic3 {
             //     withdraw: (address, withdrawn.amount().into()),
             // });
 
-            (units, withdrawn)
+            (withdrawn, claim)
+        }
+
+        /// Cancels a queued withdrawal, returning its unredeemed supply units to a position's custody.
+        ///
+        /// # Arguments
+        /// * `position_node` - A proof of the position NFT.
+        /// * `claim` - A `NonFungibleBucket` of the withdrawal claim.
+        ///
+        /// # Returns
+        /// * A `Bucket` of the assets already redeemed for the claim.
+        ///
+        /// # Panics
+        /// * If the `position_node` is invalid (see `__validate_position`).
+        /// * If the `claim` is not a withdrawal claim of an added asset.
+        pub fn position_cancel_withdrawal(&mut self, position_node: NonFungibleProof, claim: NonFungibleBucket) -> Bucket {
+            // Sanity checks
+            let (mut position, local_id) = self.__validate_position(position_node);
+            info!("[position_cancel_withdrawal] Position: {:#?}", position);
+
+            let claim_address = claim.resource_address();
+            let address = *self
+                .withdrawal_claim_to_address
+                .get(&claim_address)
+                .expect(format!("Cannot get address for withdrawal claim {:?}", claim_address).as_str());
+            let mut cluster = self.assets.get(&address).expect("Cannot get asset entry").cluster_wrapper.cluster;
+
+            let (units, withdrawn) = cluster.cancel_withdrawal(claim);
+
+            // Return the unredeemed units to the position's supply
+            if !units.is_empty() {
+                position.update_supply(&HashMap::from([(address, units.amount())]));
+                self.position_manager.update_non_fungible_data(&local_id, "supply", position.supply);
+
+                self.__deposit_position_supply(&local_id, address, units);
+            } else {
+                units.drop_empty();
+            }
+
+            withdrawn
         }
 
         //# ---------------- Debt Layer ---------------- /
@@ -445,11 +495,15 @@ mod lattic3 {
         /// Borrows resources against supply.
         ///
         /// Borrows resources from their corresponding clusters and updates the position NFT accordingly.
-        /// The operation keeps the position's health above 1.0.
+        /// The operation keeps the position's borrow health above 1.0.
+        ///
+        /// Variable-rate debt is held as debt units. Fixed-rate debt is held as one loan per asset, locked at the cluster's
+        /// fixed rate offer; borrowing more at a fixed rate adds to the existing loan.
         ///
         /// # Arguments
         /// * `position_node` - A proof of the position NFT.
         /// * `debt` - A `ValueMap` of the assets to borrow.
+        /// * `mode` - Whether to borrow at a variable or fixed rate.
         ///
         /// # Returns
         /// * A vector of `Bucket`s representing the borrowed resources.
@@ -457,37 +511,25 @@ mod lattic3 {
         /// # Panics
         /// * If the `position` is invalid (see `__validate_position`).
         /// * If some borrowed resource is invalid (see `__validate_fungible`).
+        /// * If some borrow would exceed its cluster's borrow cap.
         /// * If the maximum number of positions has been reached.
-        /// * If the operation would put the position health below 1.0.
-        pub fn position_borrow(&mut self, position_node: NonFungibleProof, debt: ValueMap) -> Vec<Bucket> {
+        /// * If the operation would put the position's borrow health below 1.0.
+        pub fn position_borrow(&mut self, position_node: NonFungibleProof, debt: ValueMap, mode: BorrowMode) -> Vec<Bucket> {
             // Sanity checks
             let (mut position, local_id) = self.__validate_position(position_node);
             info!("[position_supply] Position: {:#?}", position);
 
-            for (&address, &amount) in &debt {
-                assert!(amount > dec!(0.0), "Borrow amount must be greater than 0");
-                assert!(self.__validate_fungible(address), "Asset with address {:?} is invalid", address);
-            }
-
             // Borrow from clusters
-            let mut borrowed: Vec<Bucket> = Vec::new();
-            let mut debt_units: ValueMap = ValueMap::new();
-            for (&address, &amount) in &debt {
-                let mut asset = self.assets.get_mut(&address).expect("Cannot get asset entry");
-
-                let (debt, debt_unit) = asset.cluster_wrapper.cluster.borrow(amount);
-                borrowed.push(debt);
-                debt_units.insert(address, debt_unit);
-            }
-
-            position.update_debt(&debt_units);
+            let borrowed = self.__position_borrow(&mut position, &local_id, &debt, mode);
 
-            // Ensure that operation won't put position health below 1.0
-            let health = self.calculate_health_from_units(position.supply, position.debt.clone());
+            // Ensure that operation won't put the position's debt above its borrowing capacity
+            let (health, _) = self.__position_health(&position);
             assert!(health >= dec!(1.0), "Position health will be below 1.0. Reverting operation");
 
             // Update NFT data
             self.position_manager.update_non_fungible_data(&local_id, "debt", position.debt);
+            self.position_manager
+                .update_non_fungible_data(&local_id, "fixed_debt", position.fixed_debt);
 
             // Fire position borrow event
             // Runtime::emit_event(PositionBorrowEvent { position_id: local_id, debt, debt_units });
@@ -507,6 +549,7 @@ mod lattic3 {
         /// * `requested` - An optional `Decimal` representing the maximum amount of resources to repay.
         ///                 If not provided, the full amount will be repaid.
         ///                 Otherwise, the unredeemed supply units are returned as change.
+        /// * `mode` - Whether to repay the position's variable or fixed-rate debt.
         ///
         /// # Returns
         /// * A `Bucket` of leftover repayment units if limited by `requested`.
@@ -518,53 +561,159 @@ mod lattic3 {
         ///
         /// # Events
         /// * Emits a `PositionRepayEvent` on successful repayment
-        pub fn position_repay(&mut self, position_node: NonFungibleProof, mut repayment: Bucket, requested: Option<Decimal>) -> Bucket {
+        pub fn position_repay(&mut self, position_node: NonFungibleProof, repayment: Bucket, requested: Option<Decimal>, mode: BorrowMode) -> Bucket {
             // Sanity checks
             let (mut position, local_id) = self.__validate_position(position_node);
             info!("[position_supply] Position: {:#?}", position);
 
-            let address = repayment.resource_address();
+            let repayment = self.__position_repay(&mut position, &local_id, repayment, requested, mode);
 
-            // Ensure repayment is valid
-            assert!(!repayment.is_empty(), "Bucket for {:?} is empty", address);
-            assert!(position.debt.contains_key(&address), "Asset {:?} not borrowed", address);
+            // Fire position repay event
+            // Runtime::emit_event(PositionRepayEvent { position_id: local_id.clone(), repay: (address, amount) });
 
-            // Convert debt to debt units
-            let mut cluster = self.assets.get(&address).expect("Cannot get asset entry").cluster_wrapper.cluster;
+            // Update NFT data
+            self.position_manager.update_non_fungible_data(&local_id, "debt", position.debt);
+            self.position_manager
+                .update_non_fungible_data(&local_id, "fixed_debt", position.fixed_debt);
 
-            // Limit repayment amount by the requested amount, and by the debt owed to prevent overpayment
-            let debt = cluster.get_amount(ClusterLayer::Debt, *position.debt.get(&address).expect("Asset not borrowed"));
-            let repay_amount = repayment.amount().min(debt).min(requested.unwrap_or(Decimal::MAX));
+            repayment
+        }
 
-            // Execute repayment
-            let repay_units = cluster.repay(repayment.take(repay_amount));
+        //# -------------- Batched Actions ------------- /
 
-            // Recalculate debt
-            position.update_debt(&HashMap::from([(address, repay_units.checked_mul(dec!(-1)).unwrap())]));
+        /// Applies an ordered list of actions to a position, with a single health check once all actions are applied.
+        ///
+        /// Intermediate states may be unhealthy, as long as the final state is not; e.g. a position can withdraw
+        /// collateral to repay its debt. Resources supplied or repaid are taken from the held resources: `buckets`, along
+        /// with the resources borrowed and withdrawn by earlier actions.
+        ///
+        /// # Arguments
+        /// * `position_node` - A proof of the position NFT.
+        /// * `actions` - The `PositionAction`s to apply, in order.
+        /// * `buckets` - `Bucket`s of the resources to supply or repay.
+        ///
+        /// # Returns
+        /// * A vector of `Bucket`s of all resources left over: unused, borrowed and withdrawn resources, along with the
+        ///   withdrawal claims of queued withdrawals.
+        ///
+        /// # Panics
+        /// * If the `position_node` is invalid (see `__validate_position`).
+        /// * If some bucket holds an asset which is not listed (see `__validate_bucket`).
+        /// * If some action fails; see `position_supply`, `position_withdraw`, `position_borrow` and `position_repay`.
+        /// * If some action supplies or repays an asset which is not held.
+        /// * If the batch withdraws or borrows, and leaves the position's borrow health below 1.0.
+        pub fn position_execute(&mut self, position_node: NonFungibleProof, actions: Vec<PositionAction>, buckets: Vec<Bucket>) -> Vec<Bucket> {
+            // Sanity checks
+            let (mut position, local_id) = self.__validate_position(position_node);
+            info!("[position_execute] Position: {:#?}", position);
 
-            // Fire position repay event
-            // Runtime::emit_event(PositionRepayEvent { position_id: local_id.clone(), repay: (address, amount) });
+            assert!(!actions.is_empty(), "No actions provided");
+            for bucket in &buckets {
+                assert!(self.__validate_bucket(bucket), "Invalid bucket of {:?}", bucket.resource_address());
+            }
+
+            // Resources held by the batch, and withdrawal claims of queued withdrawals
+            let mut held: IndexMap<ResourceAddress, Bucket> = IndexMap::new();
+            let mut claims: Vec<Bucket> = Vec::new();
+
+            let hold = |held: &mut IndexMap<ResourceAddress, Bucket>, bucket: Bucket| {
+                let address = bucket.resource_address();
+                match held.get_mut(&address) {
+                    Some(existing) => {
+                        existing.put(bucket);
+                    }
+                    None => {
+                        held.insert(address, bucket);
+                    }
+                }
+            };
+
+            for bucket in buckets {
+                hold(&mut held, bucket);
+            }
+
+            // Only withdrawals and borrows can lower the position's health
+            let mut check_health = false;
+
+            for action in actions {
+                info!("[position_execute] Action: {:?}", action);
+
+                match action {
+                    PositionAction::Supply(address, amount) => {
+                        let available = held.get_mut(&address).expect(format!("No {:?} held to supply", address).as_str());
+                        let amount = amount.unwrap_or(available.amount());
+                        let supply = available.take(amount);
+                        assert!(!supply.is_empty(), "Cannot supply an empty amount of {:?}", address);
+
+                        self.__position_supply(&mut position, &local_id, vec![supply]);
+                    }
+                    PositionAction::Withdraw(address, requested) => {
+                        let (withdrawn, claim) = self.__position_withdraw(&mut position, &local_id, address, requested);
+
+                        hold(&mut held, withdrawn);
+                        if let Some(claim) = claim {
+                            claims.push(claim.into());
+                        }
+                        check_health = true;
+                    }
+                    PositionAction::Borrow(address, amount, mode) => {
+                        let debt = ValueMap::from([(address, amount)]);
+                        for borrowed in self.__position_borrow(&mut position, &local_id, &debt, mode) {
+                            hold(&mut held, borrowed);
+                        }
+                        check_health = true;
+                    }
+                    PositionAction::Repay(address, requested, mode) => {
+                        let repayment = held.swap_remove(&address).expect(format!("No {:?} held to repay", address).as_str());
+                        let change = self.__position_repay(&mut position, &local_id, repayment, requested, mode);
+
+                        hold(&mut held, change);
+                    }
+                }
+            }
+
+            // Ensure that the batch won't put the position's debt above its borrowing capacity
+            if check_health {
+                let (health, _) = self.__position_health(&position);
+                assert!(health >= dec!(1.0), "Position health will be below 1.0. Reverting operation");
+            }
 
             // Update NFT data
+            self.position_manager.update_non_fungible_data(&local_id, "supply", position.supply);
             self.position_manager.update_non_fungible_data(&local_id, "debt", position.debt);
+            self.position_manager
+                .update_non_fungible_data(&local_id, "fixed_debt", position.fixed_debt);
+
+            // Return all held resources, dropping empty buckets
+            let mut returned: Vec<Bucket> = Vec::new();
+            for (_, bucket) in held {
+                if bucket.is_empty() {
+                    bucket.drop_empty();
+                } else {
+                    returned.push(bucket);
+                }
+            }
+            returned.extend(claims);
 
-            repayment
+            returned
         }
 
         /// Retrieves the health of a specified position.
         ///
-        /// This function calculates the health of a given position using its supply and debt data
-        /// by invoking the `calculate_health_from_units` method.
+        /// This function calculates the health of a given position using its supply, debt and fixed-rate loans.
+        /// Supplied assets are weighted by their collateral parameters; see `__calculate_health`. Interest is ticked on
+        /// every cluster of the position; see the `preview_*` methods for a read-only alternative.
         ///
         /// # Arguments
         /// * `position_proof` - A proof of the position NFT used to verify and fetch the position data.
         ///
         /// # Returns
-        /// * The health of the position as a `Decimal`.
+        /// * The borrow health of the position, as a `Decimal`; the ratio of its borrowing capacity to its debt.
+        /// * The liquidation health of the position, as a `Decimal`; the position can be liquidated below 1.0.
         ///
         /// # Panics
         /// * If the `position_proof` is invalid.
-        pub fn get_position_health(&mut self, position_proof: NonFungibleProof) -> Decimal {
+        pub fn get_position_health(&mut self, position_proof: NonFungibleProof) -> (Decimal, Decimal) {
             // Sanity checks
             let position: Position = position_proof
                 .check_with_message(self.position_manager.address(), "Position check failed")
@@ -572,13 +721,15 @@ mod lattic3 {
                 .data();
             info!("[get_position_health] Position: {:#?}", position);
 
-            let health = self.calculate_health_from_units(position.supply, position.debt);
+            let health = self.__position_health(&position);
 
             health
         }
 
         /// Calculates the health of a position based from its supply and debt.
         ///
+        /// Fixed-rate loans are not included; see `get_position_health`.
+        ///
         /// <div class="warning">This function ticks the interest on all clusters in the position, and is therefore expensive.</div>
         ///
         /// # Arguments
@@ -586,112 +737,564 @@ mod lattic3 {
         /// * `debt_units` - A `ValueMap` of the borrowed asset units.
         ///
         /// # Returns
-        /// * The health of the position as a `Decimal`.
+        /// * The borrow health of the position, as a `Decimal`.
+        /// * The liquidation health of the position, as a `Decimal`.
         ///
         /// # Events
         /// * This function emits a `PositionHealthEvent` on successful health calculation.
-        pub fn calculate_health_from_units(&mut self, supply_units: ValueMap, debt_units: ValueMap) -> Decimal {
-            // Return 'infinity' if no debt taken out
-            if debt_units.is_empty() {
-                info!("[calculate_position_health] Health: Infinity {:?}", Decimal::MAX);
+        pub fn calculate_health_from_units(&mut self, supply_units: ValueMap, debt_units: ValueMap) -> (Decimal, Decimal) {
+            self.__calculate_health(supply_units, debt_units, ValueMap::new())
+        }
 
-                // Runtime::emit_event(PositionHealthEvent { health: Decimal::MAX });
-                return Decimal::MAX;
+        //] ------------------ Previews ----------------- /
+
+        /// Previews supplying assets to a position.
+        ///
+        /// Like the other `preview_*` methods, this changes no state: interest accrued since each cluster's last tick is
+        /// computed virtually, so the preview can be run from transaction previews.
+        ///
+        /// # Arguments
+        /// * `position_id` - The `NonFungibleLocalId` of the position, or `None` to preview opening a new position.
+        /// * `supply` - A `ValueMap` of the assets to supply.
+        ///
+        /// # Returns
+        /// * A `PositionPreview` of the position after the operation.
+        ///
+        /// # Panics
+        /// * If some supplied asset is invalid (see `__validate_fungible`).
+        pub fn preview_supply(&self, position_id: Option<NonFungibleLocalId>, supply: ValueMap) -> PositionPreview {
+            let (mut supplied, debt, fixed_debt) = match position_id {
+                Some(position_id) => self.__preview_position(&position_id),
+                None => (ValueMap::new(), ValueMap::new(), ValueMap::new()),
+            };
+
+            for (&address, &amount) in &supply {
+                assert!(amount > dec!(0.0), "Supply amount must be greater than 0");
+                assert!(self.__validate_fungible(address), "Asset with address {:?} is invalid", address);
+                add_to_value_map(&mut supplied, address, amount);
             }
 
-            // Tick interest on all position assets and convert them from units to amounts
-            let mut updated_addresses: IndexSet<ResourceAddress> = IndexSet::new();
+            self.__preview(supplied, debt, fixed_debt)
+        }
 
-            let supply: ValueMap = supply_units
-                .iter()
-                .map(|(&address, &unit_amount)| {
-                    if updated_addresses.insert(address) {
-                        self.assets.get_mut(&address).unwrap().cluster_wrapper.cluster.tick_interest(true);
-                    }
+        /// Previews borrowing assets against a position.
+        ///
+        /// # Arguments
+        /// * `position_id` - The `NonFungibleLocalId` of the position.
+        /// * `debt` - A `ValueMap` of the assets to borrow.
+        /// * `mode` - Whether to borrow at a variable or fixed rate.
+        ///
+        /// # Returns
+        /// * A `PositionPreview` of the position after the operation.
+        ///
+        /// # Panics
+        /// * If some borrowed asset is invalid (see `__validate_fungible`).
+        pub fn preview_borrow(&self, position_id: NonFungibleLocalId, debt: ValueMap, mode: BorrowMode) -> PositionPreview {
+            let (supplied, mut variable_debt, mut fixed_debt) = self.__preview_position(&position_id);
 
-                    let amount = self
-                        .assets
-                        .get(&address)
-                        .unwrap()
-                        .cluster_wrapper
-                        .cluster
-                        .get_amount(ClusterLayer::Supply, unit_amount);
-                    info!("Supply {:?}; units: {:?} -> amount: {:?}", address, unit_amount, amount);
-                    (address, amount)
-                })
-                .collect();
+            for (&address, &amount) in &debt {
+                assert!(amount > dec!(0.0), "Borrow amount must be greater than 0");
+                assert!(self.__validate_fungible(address), "Asset with address {:?} is invalid", address);
 
-            let debt: ValueMap = debt_units
-                .iter()
-                .map(|(&address, &unit_amount)| {
-                    if updated_addresses.insert(address) {
-                        self.assets.get_mut(&address).unwrap().cluster_wrapper.cluster.tick_interest(true);
-                    }
+                match mode {
+                    BorrowMode::Variable => add_to_value_map(&mut variable_debt, address, amount),
+                    BorrowMode::Fixed => add_to_value_map(&mut fixed_debt, address, amount),
+                }
+            }
 
-                    let amount = self
-                        .assets
-                        .get(&address)
-                        .unwrap()
-                        .cluster_wrapper
-                        .cluster
-                        .get_amount(ClusterLayer::Debt, unit_amount);
-                    info!("Debt {:?}; units: {:?} -> amount: {:?}", address, unit_amount, amount);
-                    (address, amount)
-                })
-                .collect();
+            self.__preview(supplied, variable_debt, fixed_debt)
+        }
 
-            // Calculate supply value
-            let (supply_value, _) = self.__get_asset_values(&supply);
-            info!("[calculate_position_health] Supply value: {}", supply_value);
+        /// Previews withdrawing an asset from a position's supply.
+        ///
+        /// # Arguments
+        /// * `position_id` - The `NonFungibleLocalId` of the position.
+        /// * `address` - The `ResourceAddress` of the supplied asset to withdraw.
+        /// * `requested` - An optional `Decimal` representing the maximum amount to withdraw.
+        ///                 If not provided, the position's full supply of the asset is withdrawn.
+        ///
+        /// # Returns
+        /// * A `PositionPreview` of the position after the operation.
+        ///
+        /// # Panics
+        /// * If the position does not supply the asset.
+        pub fn preview_withdraw(&self, position_id: NonFungibleLocalId, address: ResourceAddress, requested: Option<Decimal>) -> PositionPreview {
+            let (mut supplied, debt, fixed_debt) = self.__preview_position(&position_id);
 
-            // Calculate debt value
-            let (debt_value, _) = self.__get_asset_values(&debt);
-            info!("[calculate_position_health] Debt value: {}", debt_value);
+            let available = *supplied.get(&address).expect(format!("Asset {:?} not supplied", address).as_str());
+            let amount = available.min(requested.unwrap_or(Decimal::MAX));
+            add_to_value_map(&mut supplied, address, amount.checked_mul(dec!(-1)).unwrap());
 
-            // Sanity check
-            assert!(
-                debt_value > dec!(0.0),
-                "Debt value must be greater than 0, I don't know how we got here. Debt: {:?}",
-                debt
-            );
+            self.__preview(supplied, debt, fixed_debt)
+        }
 
-            // health = (supply / debt), * 100 for display
-            let health = supply_value.checked_div(debt_value).unwrap();
-            info!("[calculate_position_health] Health: {:?}", health);
+        /// Previews repaying a position's debt.
+        ///
+        /// # Arguments
+        /// * `position_id` - The `NonFungibleLocalId` of the position.
+        /// * `address` - The `ResourceAddress` of the borrowed asset to repay.
+        /// * `amount` - The amount to repay; limited to the amount owed.
+        /// * `mode` - Whether to repay the position's variable or fixed-rate debt.
+        ///
+        /// # Returns
+        /// * A `PositionPreview` of the position after the operation.
+        ///
+        /// # Panics
+        /// * If the position holds no debt in the asset at the given rate.
+        pub fn preview_repay(&self, position_id: NonFungibleLocalId, address: ResourceAddress, amount: Decimal, mode: BorrowMode) -> PositionPreview {
+            let (supplied, mut variable_debt, mut fixed_debt) = self.__preview_position(&position_id);
 
-            // Fire health event
-            // Runtime::emit_event(PositionHealthEvent { health });
+            let owed_debt = match mode {
+                BorrowMode::Variable => &mut variable_debt,
+                BorrowMode::Fixed => &mut fixed_debt,
+            };
+            let owed = *owed_debt
+                .get(&address)
+                .expect(format!("Asset {:?} not borrowed at a {:?} rate", address, mode).as_str());
+            add_to_value_map(owed_debt, address, owed.min(amount).checked_mul(dec!(-1)).unwrap());
 
-            health
+            self.__preview(supplied, variable_debt, fixed_debt)
         }
 
-        //] --------------- Asset Listing -------------- /
+        //] ---------------- Liquidation --------------- /
 
-        /// Add a fungible asset into the market, and output a FungibleAsset struct
-        pub fn add_asset(&mut self, address: ResourceAddress) {
-            info!("[add_asset] Adding asset: {:?}", address);
+        /// Liquidates part of an unhealthy position's debt in exchange for its collateral.
+        ///
+        /// The liquidator repays debt of the position in the asset of `repayment`; variable-rate debt is repaid first, then
+        /// the fixed-rate loan. In return, the liquidator receives collateral worth the repaid value plus a bonus, at oracle
+        /// prices. If the position holds too little of the collateral asset, the repayment is reduced to what the
+        /// collateral covers.
+        ///
+        /// In `FixedBonus` mode, up to `close_factor` of the position's debt in the asset is repaid, at the collateral
+        /// asset's `liquidation_bonus`. In `Auction` mode, the position's auction must have been started through
+        /// `start_auction`; its debt can be repaid in full, at the auction's current bonus, capped so seizing the
+        /// collateral asset cannot lower the position's liquidation health. The auction ends once the position is healthy
+        /// again, or holds no more collateral; once it has run past its duration, it expires and must be restarted.
+        ///
+        /// The seized supply units are taken from the position's custody, and burnt as the collateral is withdrawn. If the
+        /// collateral cluster's liquidity cannot pay out the seized collateral, its withdrawal is queued instead, as in
+        /// `position_withdraw`.
+        ///
+        /// # Arguments
+        /// * `position_id` - The `NonFungibleLocalId` of the position to liquidate.
+        /// * `repayment` - A `Bucket` of the borrowed asset to repay.
+        /// * `collateral_asset` - The `ResourceAddress` of the supplied asset to seize.
+        ///
+        /// # Returns
+        /// * A `Bucket` of the seized collateral; empty if its withdrawal was queued.
+        /// * A `NonFungibleBucket` of the withdrawal claim, if the withdrawal was queued.
+        /// * A `Bucket` of the unused repayment.
+        ///
+        /// # Panics
+        /// * If the position is healthy (liquidation health of at least 1.0).
+        /// * If the market liquidates by auction, and no auction is running for the position, or it has expired.
+        /// * If the position holds no debt in the repaid asset, or no supply of the collateral asset.
+        /// * If the repayment bucket is empty.
+        ///
+        /// # Events
+        /// * Emits a `LiquidationEvent` on successful liquidation.
+        /// * Emits an `AuctionEndEvent` if the liquidation ends the position's auction.
+        pub fn liquidate_position(
+            &mut self,
+            position_id: NonFungibleLocalId,
+            mut repayment: Bucket,
+            collateral_asset: ResourceAddress,
+        ) -> (Bucket, Option<NonFungibleBucket>, Bucket) {
+            let mut position: Position = self.position_manager.get_non_fungible_data(&position_id);
+            info!("[liquidate_position] Position: {:#?}", position);
+
+            let debt_asset = repayment.resource_address();
+            assert!(!repayment.is_empty(), "Bucket for {:?} is empty", debt_asset);
+
+            let debt_units = position.debt.get(&debt_asset).copied().unwrap_or(dec!(0.0));
+            let fixed_loan = position.fixed_debt.get(&debt_asset).cloned();
+            assert!(
+                debt_units > dec!(0.0) || fixed_loan.is_some(),
+                "Position {:?} holds no debt in {:?}",
+                position_id,
+                debt_asset
+            );
 
-            // Sanity checks
-            assert!(address.is_fungible(), "Provided asset must be fungible.");
-            assert!(self.assets.get(&address).is_none(), "Asset already has an entry");
+            let collateral_units = *position
+                .supply
+                .get(&collateral_asset)
+                .expect(format!("Position {:?} holds no supply of {:?}", position_id, collateral_asset).as_str());
+
+            // Ticks interest on every cluster of the position, so the amounts below are up to date
+            let (_, health) = self.__position_health(&position);
             assert!(
-                !self.__validate_fungible(address),
-                "Cannot add asset {:?}, as it is already added and tracked",
-                address
+                health < dec!(1.0),
+                "Position {:?} is healthy (health: {}); cannot liquidate",
+                position_id,
+                health
             );
 
-            // Cluster owned: Lattic3 owner
-            // Cluster admin: Lattic3 owner or Lattic3 market component calls
-            let cluster_owner = rule!(require(self.owner_badge_address));
-            let cluster_manager = rule!(require(global_caller(self.component_address)) || require(self.owner_badge_address));
-            let cluster_wrapper = ClusterWrapper::create(address, cluster_owner, cluster_manager);
+            let mut debt_cluster = self.assets.get(&debt_asset).expect("Cannot get asset entry").cluster_wrapper.cluster;
+            let collateral_entry = self.assets.get(&collateral_asset).expect("Cannot get asset entry").clone();
+            let mut collateral_cluster = collateral_entry.cluster_wrapper.cluster;
+
+            // Bonus paid to the liquidator, and the share of the debt repayable; auctions can be filled in full
+            let (bonus, close_factor) = match self.liquidation_mode {
+                LiquidationMode::FixedBonus => (collateral_entry.liquidation_bonus, self.close_factor),
+                LiquidationMode::Auction => {
+                    let auction = self
+                        .auctions
+                        .get(&position_id)
+                        .expect(format!("No auction running for position {:?}", position_id).as_str());
+                    let current_time = now();
+                    assert!(
+                        !auction.is_expired(current_time),
+                        "Auction for position {:?} has expired; restart it through start_auction",
+                        position_id
+                    );
+                    (collateral_entry.cap_liquidation_bonus(auction.bonus(current_time)), dec!(1.0))
+                }
+            };
 
-            // Create FungibleAsset
-            let asset = AssetEntry::new(address, cluster_wrapper);
-            info!("[add_asset] FungibleAsset: {:#?}", asset);
+            // Debt owed in the repaid asset; rounded up, as it is owed to the cluster
+            let variable_debt = if debt_units > dec!(0.0) {
+                debt_cluster.get_amount(ClusterLayer::Debt, debt_units, Rounding::Up)
+            } else {
+                dec!(0.0)
+            };
+            let fixed_debt = fixed_loan.map(|loan_id| debt_cluster.get_fixed_debt(loan_id)).unwrap_or(dec!(0.0));
+            let owed = variable_debt.checked_add(fixed_debt).unwrap();
 
-            // Fire AddAssetEvent
-            Runtime::emit_event(AddAssetEvent {
+            // Limit the repayment by the close factor
+            let mut repay_amount = repayment.amount().min(owed.checked_mul(close_factor).unwrap());
+
+            // Value the repayment and collateral at oracle prices
+            let price_stream = self.__price_stream();
+            let debt_price = price_stream
+                .get_price(debt_asset)
+                .expect(format!("Unable to get price of {:?}", debt_asset).as_str());
+            let collateral_price = price_stream
+                .get_price(collateral_asset)
+                .expect(format!("Unable to get price of {:?}", collateral_asset).as_str());
+            assert!(
+                debt_price > ZERO_PRICE && collateral_price > ZERO_PRICE,
+                "Cannot liquidate against a zero price (debt: {}, collateral: {})",
+                debt_price,
+                collateral_price
+            );
+
+            // Collateral owed to the liquidator: the repaid value plus the bonus; rounded down, in the position's favour
+            let bonus_factor = dec!(1.0).checked_add(bonus).unwrap();
+            let repaid_value = repay_amount.checked_mul(debt_price).unwrap().checked_mul(bonus_factor).unwrap();
+            let mut seize_amount = repaid_value.checked_div(collateral_price).unwrap();
+
+            let collateral = collateral_cluster.get_amount(ClusterLayer::Supply, collateral_units, Rounding::Down);
+            let mut seize_all = seize_amount >= collateral;
+            if seize_all {
+                // Only repay what the remaining collateral covers
+                seize_amount = collateral;
+                repay_amount = collateral
+                    .checked_mul(collateral_price)
+                    .unwrap()
+                    .checked_div(debt_price.checked_mul(bonus_factor).unwrap())
+                    .unwrap();
+            }
+            assert!(repay_amount > dec!(0.0) && seize_amount > dec!(0.0), "Nothing to liquidate");
+
+            // Repay variable-rate debt first; the cluster burns the repaid debt units out of the position's custody
+            let variable_repay = repay_amount.min(variable_debt);
+            if variable_repay > dec!(0.0) {
+                let held_units = self.__take_position_debt(&position_id, debt_asset);
+                let held_amount = held_units.amount();
+
+                let remaining_units = debt_cluster.repay(repayment.take(variable_repay), held_units);
+                let repay_units = held_amount.checked_sub(remaining_units.amount()).unwrap();
+
+                self.__deposit_position_debt(&position_id, debt_asset, remaining_units);
+                position.update_debt(&HashMap::from([(debt_asset, repay_units.checked_mul(dec!(-1)).unwrap())]));
+            }
+
+            // Repay the rest against the fixed-rate loan; a fully repaid loan is burnt by the cluster
+            let mut fixed_repay = repay_amount.checked_sub(variable_repay).unwrap().min(fixed_debt);
+            if fixed_repay > dec!(0.0) {
+                let loan = self.__take_position_fixed_debt(&position_id, debt_asset);
+                let (change, loan) = debt_cluster.repay_fixed(repayment.take(fixed_repay), loan);
+                fixed_repay = fixed_repay.checked_sub(change.amount()).unwrap();
+                repayment.put(change);
+
+                match loan {
+                    Some(loan) => self.__deposit_position_fixed_debt(&position_id, debt_asset, loan),
+                    None => {
+                        position.fixed_debt.remove(&debt_asset);
+                    }
+                }
+            }
+
+            // Only pay out collateral for what was actually repaid, if the cluster returned change
+            let repaid = variable_repay.checked_add(fixed_repay).unwrap();
+            if repaid < repay_amount {
+                seize_all = false;
+                seize_amount = repaid
+                    .checked_mul(debt_price)
+                    .unwrap()
+                    .checked_mul(bonus_factor)
+                    .unwrap()
+                    .checked_div(collateral_price)
+                    .unwrap();
+            }
+
+            // Seize the collateral's supply units; rounded down, in the position's favour, unless all of it is seized
+            let seize_units = if seize_all {
+                collateral_units
+            } else {
+                collateral_cluster
+                    .get_units(ClusterLayer::Supply, seize_amount, Rounding::Down)
+                    .min(collateral_units)
+            };
+            position.update_supply(&HashMap::from([(collateral_asset, seize_units.checked_mul(dec!(-1)).unwrap())]));
+            let units = self.__take_position_supply(&position_id, collateral_asset, seize_units);
+
+            // Withdraw the collateral for the liquidator, or queue the withdrawal if the cluster cannot pay it out
+            let state: ClusterState = collateral_cluster.get_cluster_state();
+            let seized = collateral_cluster.get_amount(ClusterLayer::Supply, seize_units, Rounding::Down);
+
+            let (withdrawn, claim) = if state.queued_units == dec!(0.0) && seized <= state.liquidity {
+                (collateral_cluster.withdraw(units), None)
+            } else {
+                info!(
+                    "[liquidate_position] Liquidity of {} does not cover {}; queueing withdrawal",
+                    state.liquidity, seized
+                );
+                (Bucket::new(collateral_asset), Some(collateral_cluster.request_withdrawal(units)))
+            };
+
+            // End the position's auction once it is healthy again, or has no more collateral to auction
+            if self.liquidation_mode == LiquidationMode::Auction {
+                let (_, health_after) = self.__position_health(&position);
+                if health_after >= dec!(1.0) || position.supply.is_empty() {
+                    self.auctions.remove(&position_id);
+                    Runtime::emit_event(AuctionEndEvent { position_id: position_id.clone(), health: health_after });
+                }
+            }
+
+            // Update NFT data
+            self.position_manager.update_non_fungible_data(&position_id, "supply", position.supply);
+            self.position_manager.update_non_fungible_data(&position_id, "debt", position.debt);
+            self.position_manager
+                .update_non_fungible_data(&position_id, "fixed_debt", position.fixed_debt);
+
+            Runtime::emit_event(LiquidationEvent {
+                position_id,
+                health,
+                debt_asset,
+                repaid,
+                collateral_asset,
+                seized,
+                seized_units: seize_units,
+                bonus,
+            });
+
+            (withdrawn, claim, repayment)
+        }
+
+        /// Puts an unhealthy position up for auction, when the market liquidates by auction.
+        ///
+        /// The auction's bonus starts at the market's `auction_config.start_bonus`, and grows to its `max_bonus` over its
+        /// `duration`; see `liquidate_position` to fill it. An auction which has run past its duration has expired, and is
+        /// restarted, so a position which recovered and became unhealthy again is not auctioned at a stale bonus.
+        ///
+        /// # Arguments
+        /// * `position_id` - The `NonFungibleLocalId` of the position to auction.
+        ///
+        /// # Panics
+        /// * If the market liquidates at a fixed bonus.
+        /// * If an auction is already running for the position, and has not expired.
+        /// * If the position is healthy (liquidation health of at least 1.0).
+        ///
+        /// # Events
+        /// * Emits an `AuctionStartEvent` on success.
+        pub fn start_auction(&mut self, position_id: NonFungibleLocalId) {
+            assert!(
+                self.liquidation_mode == LiquidationMode::Auction,
+                "Liquidation auctions are disabled; positions are liquidated at a fixed bonus"
+            );
+            if let Some(auction) = self.auctions.get(&position_id) {
+                assert!(auction.is_expired(now()), "Auction already running for position {:?}", position_id);
+            }
+
+            let position: Position = self.position_manager.get_non_fungible_data(&position_id);
+            let (_, health) = self.__position_health(&position);
+            assert!(
+                health < dec!(1.0),
+                "Position {:?} is healthy (health: {}); cannot auction",
+                position_id,
+                health
+            );
+
+            let auction = LiquidationAuction::new(now(), self.auction_config);
+            self.auctions.insert(position_id.clone(), auction);
+
+            Runtime::emit_event(AuctionStartEvent { position_id, health, config: self.auction_config });
+        }
+
+        /// Ends the auction of a position which is healthy again, or any auction once the market liquidates at a fixed
+        /// bonus.
+        ///
+        /// # Arguments
+        /// * `position_id` - The `NonFungibleLocalId` of the auctioned position.
+        ///
+        /// # Panics
+        /// * If no auction is running for the position.
+        /// * If the market liquidates by auction, and the position is still unhealthy.
+        ///
+        /// # Events
+        /// * Emits an `AuctionEndEvent` on success.
+        pub fn end_auction(&mut self, position_id: NonFungibleLocalId) {
+            assert!(
+                self.auctions.get(&position_id).is_some(),
+                "No auction running for position {:?}",
+                position_id
+            );
+
+            let position: Position = self.position_manager.get_non_fungible_data(&position_id);
+            let (_, health) = self.__position_health(&position);
+            assert!(
+                self.liquidation_mode == LiquidationMode::FixedBonus || health >= dec!(1.0),
+                "Position {:?} is still unhealthy (health: {}); cannot end its auction",
+                position_id,
+                health
+            );
+
+            self.auctions.remove(&position_id);
+            Runtime::emit_event(AuctionEndEvent { position_id, health });
+        }
+
+        /// Sets how the market liquidates unhealthy positions.
+        ///
+        /// Auctions left running when switching to `FixedBonus` are ignored, and can be ended through `end_auction`.
+        ///
+        /// # Arguments
+        /// * `mode` - The new `LiquidationMode`.
+        pub fn set_liquidation_mode(&mut self, mode: LiquidationMode) {
+            self.liquidation_mode = mode;
+        }
+
+        /// Sets the parameters of liquidation auctions; running auctions keep the parameters they started with.
+        ///
+        /// # Arguments
+        /// * `config` - The new `AuctionConfig`.
+        ///
+        /// # Panics
+        /// * If the parameters are invalid (see `AuctionConfig::validate`).
+        pub fn set_auction_config(&mut self, config: AuctionConfig) {
+            config.validate();
+            self.auction_config = config;
+        }
+
+        /// Sets the maximum share of a position's debt in an asset which a single liquidation can repay.
+        ///
+        /// # Arguments
+        /// * `close_factor` - The new close factor.
+        ///
+        /// # Panics
+        /// * If the close factor is not within (0, 1].
+        pub fn set_close_factor(&mut self, close_factor: Decimal) {
+            assert!(
+                close_factor > dec!(0.0) && close_factor <= dec!(1.0),
+                "Close factor must be between 0 (exclusive) and 1 (inclusive)"
+            );
+
+            self.close_factor = close_factor;
+        }
+
+        /// Sets the collateral parameters of an asset.
+        ///
+        /// # Arguments
+        /// * `address` - The `ResourceAddress` of the asset.
+        /// * `ltv` - Share of the asset's supplied value which can be borrowed against.
+        /// * `liquidation_threshold` - Share of the asset's supplied value counted towards liquidation health.
+        /// * `liquidation_bonus` - Share of the repaid value paid to liquidators on top, when seizing the asset.
+        ///
+        /// # Panics
+        /// * If the asset is not added.
+        /// * If the parameters are invalid (see `AssetEntry::set_collateral_parameters`).
+        pub fn set_collateral_parameters(
+            &mut self,
+            address: ResourceAddress,
+            ltv: Decimal,
+            liquidation_threshold: Decimal,
+            liquidation_bonus: Decimal,
+        ) {
+            let mut entry = self.assets.get_mut(&address).expect("Cannot get asset entry");
+            entry.set_collateral_parameters(ltv, liquidation_threshold, liquidation_bonus);
+        }
+
+        //] -------------- Loss Management ------------- /
+
+        /// Writes off the remaining debt of a position which no longer holds any collateral.
+        ///
+        /// The debt units and fixed-rate loan held for the position are handed to the asset's cluster, which absorbs the
+        /// loss.
+        ///
+        /// # Arguments
+        /// * `local_id` - The `NonFungibleLocalId` of the position.
+        /// * `address` - The `ResourceAddress` of the borrowed asset to write off.
+        ///
+        /// # Panics
+        /// * If the position still holds collateral.
+        /// * If the position holds no debt in the given asset, at a variable or fixed rate.
+        pub fn absorb_bad_debt(&mut self, local_id: NonFungibleLocalId, address: ResourceAddress) {
+            let mut position: Position = self.position_manager.get_non_fungible_data(&local_id);
+            info!("[absorb_bad_debt] Position: {:#?}", position);
+
+            assert!(position.supply.is_empty(), "Position {:?} still holds collateral", local_id);
+            let units = position.debt.get(&address).copied();
+            let has_fixed_debt = position.fixed_debt.contains_key(&address);
+            assert!(units.is_some() || has_fixed_debt, "Asset not borrowed");
+
+            let mut cluster = self.assets.get(&address).expect("Cannot get asset entry").cluster_wrapper.cluster;
+
+            // Remove the written-off debt
+            if let Some(units) = units {
+                cluster.absorb_bad_debt(self.__take_position_debt(&local_id, address));
+                position.update_debt(&HashMap::from([(address, units.checked_mul(dec!(-1)).unwrap())]));
+            }
+            if has_fixed_debt {
+                cluster.absorb_bad_fixed_debt(self.__take_position_fixed_debt(&local_id, address));
+                position.fixed_debt.remove(&address);
+            }
+
+            self.position_manager.update_non_fungible_data(&local_id, "debt", position.debt);
+            self.position_manager
+                .update_non_fungible_data(&local_id, "fixed_debt", position.fixed_debt);
+        }
+
+        //] --------------- Asset Listing -------------- /
+
+        /// Add a fungible asset into the market, and output a FungibleAsset struct
+        ///
+        /// # Arguments
+        /// * `address` - Address of the asset to add.
+        /// * `config` - Configuration of the asset's cluster; the cluster's defaults are used if not provided.
+        pub fn add_asset(&mut self, address: ResourceAddress, config: Option<ClusterConfig>) {
+            info!("[add_asset] Adding asset: {:?}", address);
+
+            // Sanity checks
+            assert!(address.is_fungible(), "Provided asset must be fungible.");
+            assert!(self.assets.get(&address).is_none(), "Asset already has an entry");
+            assert!(
+                !self.__validate_fungible(address),
+                "Cannot add asset {:?}, as it is already added and tracked",
+                address
+            );
+
+            // Cluster owned: Lattic3 owner
+            // Cluster admin: Lattic3 owner or Lattic3 market component calls
+            let cluster_owner = rule!(require(self.owner_badge_address));
+            let cluster_manager = rule!(require(global_caller(self.component_address)) || require(self.owner_badge_address));
+            let cluster_wrapper = ClusterWrapper::create(address, config, cluster_owner, cluster_manager);
+
+            // Create FungibleAsset
+            let asset = AssetEntry::new(address, cluster_wrapper);
+            info!("[add_asset] FungibleAsset: {:#?}", asset);
+
+            // Fire AddAssetEvent
+            Runtime::emit_event(AddAssetEvent {
                 asset: address,
                 cluster_address: asset.cluster_wrapper.cluster.address(),
                 supply_unit_address: asset.cluster_wrapper.supply_unit,
@@ -700,6 +1303,7 @@ mod lattic3 {
             self.assets.insert(address, asset.clone());
             self.address_to_supply_unit.insert(address, asset.cluster_wrapper.supply_unit);
             self.supply_unit_to_address.insert(asset.cluster_wrapper.supply_unit, address);
+            self.withdrawal_claim_to_address.insert(asset.cluster_wrapper.withdrawal_claim, address);
             self.track_asset(address);
         }
 
@@ -736,6 +1340,26 @@ mod lattic3 {
             }
         }
 
+        /// Points an asset at a migrated cluster, created through the cluster's `instantiate_from_state`.
+        ///
+        /// # Panics
+        /// * If the asset is not added.
+        /// * If the new cluster does not hold the same resource, or use the same supply and debt units.
+        pub fn migrate_cluster(&mut self, asset: ResourceAddress, cluster_address: ComponentAddress) {
+            info!("[migrate_cluster] Migrating asset {:?} to cluster {:?}", asset, cluster_address);
+
+            let mut entry = self.assets.get_mut(&asset).expect("Cannot get asset entry");
+            let cluster_wrapper = ClusterWrapper::new(cluster_address);
+
+            assert!(cluster_wrapper.resource == asset, "Cluster holds a different resource");
+            assert!(
+                cluster_wrapper.supply_unit == entry.cluster_wrapper.supply_unit && cluster_wrapper.debt_unit == entry.cluster_wrapper.debt_unit,
+                "Cluster does not use the asset's supply and debt units"
+            );
+
+            entry.cluster_wrapper = cluster_wrapper;
+        }
+
         //] ---------- Price Stream Management --------- /
         pub fn link_price_stream(&mut self, price_stream_address: ComponentAddress) {
             self.price_stream_address = Some(price_stream_address);
@@ -813,6 +1437,179 @@ mod lattic3 {
             true
         }
 
+        /// Supplies buckets to clusters, holding the supply units in custody for the position
+        fn __position_supply(&mut self, position: &mut Position, local_id: &NonFungibleLocalId, supply: Vec<Bucket>) {
+            let mut unit_map: ValueMap = HashMap::new();
+
+            for bucket in supply {
+                let address = bucket.resource_address();
+                self.__assert_within_supply_cap(address, bucket.amount());
+                let mut cluster = self.assets.get(&address).expect("Cannot get asset entry").cluster_wrapper.cluster;
+
+                let pool_unit = cluster.supply(bucket);
+                add_to_value_map(&mut unit_map, address, pool_unit.amount());
+
+                // Hold the supply units in custody for the position
+                self.__deposit_position_supply(local_id, address, pool_unit);
+            }
+
+            position.update_supply(&unit_map);
+        }
+
+        /// Withdraws a position's supply of an asset, or queues the withdrawal; see `position_withdraw`
+        fn __position_withdraw(
+            &mut self,
+            position: &mut Position,
+            local_id: &NonFungibleLocalId,
+            address: ResourceAddress,
+            requested: Option<Decimal>,
+        ) -> (Bucket, Option<NonFungibleBucket>) {
+            let supplied_units = *position.supply.get(&address).expect(format!("Asset {:?} not supplied", address).as_str());
+            let mut cluster = self.assets.get(&address).expect("Cannot get asset entry").cluster_wrapper.cluster;
+
+            // Tick interest first, so that the requested amount and the withdrawn units are converted at the same ratio
+            cluster.tick_interest(true);
+
+            // If requested is Some, limit the amount of units withdrawn by the unit amount of requested; rounded up, so the
+            // requested amount is covered in full
+            let requested_units: Decimal = if let Some(amount) = requested {
+                cluster.get_units(ClusterLayer::Supply, amount, Rounding::Up)
+            } else {
+                Decimal::MAX
+            };
+            let unit_amount: Decimal = supplied_units.min(requested_units);
+            let units = self.__take_position_supply(local_id, address, unit_amount);
+
+            // Recalculate supply
+            position.update_supply(&HashMap::from([(address, unit_amount.checked_mul(dec!(-1)).unwrap())]));
+
+            // Withdraw from cluster, or queue the withdrawal if the cluster cannot pay it out
+            let state: ClusterState = cluster.get_cluster_state();
+            let amount = cluster.get_amount(ClusterLayer::Supply, unit_amount, Rounding::Down);
+
+            if state.queued_units == dec!(0.0) && amount <= state.liquidity {
+                (cluster.withdraw(units), None)
+            } else {
+                info!(
+                    "[position_withdraw] Liquidity of {} does not cover {}; queueing withdrawal",
+                    state.liquidity, amount
+                );
+                (Bucket::new(address), Some(cluster.request_withdrawal(units)))
+            }
+        }
+
+        /// Borrows assets for a position, holding the debt units or loans in custody; see `position_borrow`
+        fn __position_borrow(&mut self, position: &mut Position, local_id: &NonFungibleLocalId, debt: &ValueMap, mode: BorrowMode) -> Vec<Bucket> {
+            for (&address, &amount) in debt {
+                assert!(amount > dec!(0.0), "Borrow amount must be greater than 0");
+                assert!(self.__validate_fungible(address), "Asset with address {:?} is invalid", address);
+                self.__assert_within_borrow_cap(address, amount);
+            }
+
+            let mut borrowed: Vec<Bucket> = Vec::new();
+            let mut debt_units: ValueMap = ValueMap::new();
+            for (&address, &amount) in debt {
+                let mut cluster = self.assets.get(&address).expect("Cannot get asset entry").cluster_wrapper.cluster;
+
+                match mode {
+                    BorrowMode::Variable => {
+                        let (debt, debt_unit) = cluster.borrow(amount);
+                        borrowed.push(debt);
+                        debt_units.insert(address, debt_unit.amount());
+
+                        // Hold the debt units in custody for the position
+                        self.__deposit_position_debt(local_id, address, debt_unit);
+                    }
+                    BorrowMode::Fixed => {
+                        // Add to the position's existing loan for the asset, if any
+                        let existing = if position.fixed_debt.contains_key(&address) {
+                            Some(self.__take_position_fixed_debt(local_id, address))
+                        } else {
+                            None
+                        };
+
+                        let (debt, loan) = cluster.borrow_fixed(amount, existing);
+                        borrowed.push(debt);
+                        position.fixed_debt.insert(address, loan.non_fungible_local_id());
+
+                        // Hold the loan in custody for the position
+                        self.__deposit_position_fixed_debt(local_id, address, loan);
+                    }
+                }
+            }
+
+            position.update_debt(&debt_units);
+
+            borrowed
+        }
+
+        /// Repays a position's variable or fixed-rate debt, returning the unused repayment; see `position_repay`
+        fn __position_repay(
+            &mut self,
+            position: &mut Position,
+            local_id: &NonFungibleLocalId,
+            mut repayment: Bucket,
+            requested: Option<Decimal>,
+            mode: BorrowMode,
+        ) -> Bucket {
+            let address = repayment.resource_address();
+
+            // Ensure repayment is valid
+            assert!(!repayment.is_empty(), "Bucket for {:?} is empty", address);
+
+            let mut cluster = self.assets.get(&address).expect("Cannot get asset entry").cluster_wrapper.cluster;
+
+            if mode == BorrowMode::Fixed {
+                let loan_id = position
+                    .fixed_debt
+                    .get(&address)
+                    .expect(format!("Asset {:?} not borrowed at a fixed rate", address).as_str())
+                    .clone();
+
+                // Limit repayment amount by the requested amount, and by the amount owed on the loan
+                let owed = cluster.get_fixed_debt(loan_id);
+                let repay_amount = repayment.amount().min(owed).min(requested.unwrap_or(Decimal::MAX));
+
+                // Execute repayment; a fully repaid loan is burnt by the cluster
+                let loan = self.__take_position_fixed_debt(local_id, address);
+                let (change, loan) = cluster.repay_fixed(repayment.take(repay_amount), loan);
+                repayment.put(change);
+
+                match loan {
+                    Some(loan) => self.__deposit_position_fixed_debt(local_id, address, loan),
+                    None => {
+                        position.fixed_debt.remove(&address);
+                    }
+                }
+
+                return repayment;
+            }
+
+            assert!(position.debt.contains_key(&address), "Asset {:?} not borrowed", address);
+
+            // Limit repayment amount by the requested amount, and by the debt owed (rounded up) to prevent overpayment
+            let debt = cluster.get_amount(
+                ClusterLayer::Debt,
+                *position.debt.get(&address).expect("Asset not borrowed"),
+                Rounding::Up,
+            );
+            let repay_amount = repayment.amount().min(debt).min(requested.unwrap_or(Decimal::MAX));
+
+            // Execute repayment; the cluster burns the repaid debt units out of the position's custody
+            let held_units = self.__take_position_debt(local_id, address);
+            let held_amount = held_units.amount();
+
+            let remaining_units = cluster.repay(repayment.take(repay_amount), held_units);
+            let repay_units = held_amount.checked_sub(remaining_units.amount()).unwrap();
+
+            self.__deposit_position_debt(local_id, address, remaining_units);
+
+            // Recalculate debt
+            position.update_debt(&HashMap::from([(address, repay_units.checked_mul(dec!(-1)).unwrap())]));
+
+            repayment
+        }
+
         fn __validate_position(&self, position: NonFungibleProof) -> (Position, NonFungibleLocalId) {
             let checked = position.check_with_message(self.position_manager.address(), "Position check failed");
             let local_id = checked.non_fungible_local_id();
@@ -821,6 +1618,294 @@ mod lattic3 {
             (position, local_id)
         }
 
+        /// Checks that supplying `amount` of the asset keeps its cluster within the supply cap
+        fn __assert_within_supply_cap(&self, address: ResourceAddress, amount: Decimal) {
+            let asset = self.assets.get(&address).expect("Cannot get asset entry");
+            let state: ClusterState = asset.cluster_wrapper.cluster.get_cluster_state();
+
+            if let Some(cap) = state.supply_cap {
+                assert!(
+                    state.virtual_supply.checked_add(amount).unwrap() <= PreciseDecimal::from(cap),
+                    "Cannot supply {} {}; the supply cap of {} would be exceeded (currently supplied: {})",
+                    amount,
+                    asset.symbol,
+                    cap,
+                    state.virtual_supply
+                );
+            }
+        }
+
+        /// Checks that borrowing `amount` of the asset keeps its cluster within the borrow cap
+        fn __assert_within_borrow_cap(&self, address: ResourceAddress, amount: Decimal) {
+            let asset = self.assets.get(&address).expect("Cannot get asset entry");
+            let state: ClusterState = asset.cluster_wrapper.cluster.get_cluster_state();
+
+            if let Some(cap) = state.borrow_cap {
+                let borrowed = state.virtual_debt.checked_add(state.virtual_fixed_debt).unwrap();
+                assert!(
+                    borrowed.checked_add(amount).unwrap() <= PreciseDecimal::from(cap),
+                    "Cannot borrow {} {}; the borrow cap of {} would be exceeded (currently borrowed: {})",
+                    amount,
+                    asset.symbol,
+                    cap,
+                    borrowed
+                );
+            }
+        }
+
+        /// Deposits a fixed-rate loan into the position's custody vault for the given asset
+        fn __deposit_position_fixed_debt(&mut self, local_id: &NonFungibleLocalId, address: ResourceAddress, loan: NonFungibleBucket) {
+            let key = (local_id.clone(), address);
+
+            if let Some(mut vault) = self.position_fixed_debt.get_mut(&key) {
+                vault.put(loan);
+                return;
+            }
+
+            self.position_fixed_debt.insert(key, NonFungibleVault::with_bucket(loan));
+        }
+
+        /// Takes the fixed-rate loan of the given asset out of the position's custody vault
+        fn __take_position_fixed_debt(&mut self, local_id: &NonFungibleLocalId, address: ResourceAddress) -> NonFungibleBucket {
+            let mut vault = self
+                .position_fixed_debt
+                .get_mut(&(local_id.clone(), address))
+                .expect(format!("No fixed-rate loan held for position {:?} and asset {:?}", local_id, address).as_str());
+
+            vault.take_all()
+        }
+
+        /// Deposits supply units into the position's custody vault for the given asset
+        fn __deposit_position_supply(&mut self, local_id: &NonFungibleLocalId, address: ResourceAddress, units: Bucket) {
+            let key = (local_id.clone(), address);
+
+            if let Some(mut vault) = self.position_supply.get_mut(&key) {
+                vault.put(units);
+                return;
+            }
+
+            self.position_supply.insert(key, Vault::with_bucket(units));
+        }
+
+        /// Takes supply units of the given asset out of the position's custody vault
+        fn __take_position_supply(&mut self, local_id: &NonFungibleLocalId, address: ResourceAddress, amount: Decimal) -> Bucket {
+            let mut vault = self
+                .position_supply
+                .get_mut(&(local_id.clone(), address))
+                .expect(format!("No supply units held for position {:?} and asset {:?}", local_id, address).as_str());
+
+            vault.take(amount)
+        }
+
+        /// Deposits debt units into the position's custody vault for the given asset
+        fn __deposit_position_debt(&mut self, local_id: &NonFungibleLocalId, address: ResourceAddress, units: Bucket) {
+            let key = (local_id.clone(), address);
+
+            if let Some(mut vault) = self.position_debt.get_mut(&key) {
+                vault.put(units);
+                return;
+            }
+
+            self.position_debt.insert(key, Vault::with_bucket(units));
+        }
+
+        /// Takes all debt units of the given asset out of the position's custody vault
+        fn __take_position_debt(&mut self, local_id: &NonFungibleLocalId, address: ResourceAddress) -> Bucket {
+            let mut vault = self
+                .position_debt
+                .get_mut(&(local_id.clone(), address))
+                .expect(format!("No debt units held for position {:?} and asset {:?}", local_id, address).as_str());
+
+            vault.take_all()
+        }
+
+        /// Calculates the health of a position, including its fixed-rate loans
+        fn __position_health(&mut self, position: &Position) -> (Decimal, Decimal) {
+            let fixed_debt: ValueMap = position
+                .fixed_debt
+                .iter()
+                .map(|(&address, loan_id)| {
+                    let amount = self.assets.get(&address).unwrap().cluster_wrapper.cluster.get_fixed_debt(loan_id.clone());
+                    (address, amount)
+                })
+                .collect();
+
+            self.__calculate_health(position.supply.clone(), position.debt.clone(), fixed_debt)
+        }
+
+        /// Calculates the health from supply and debt units, and fixed-rate debt amounts
+        ///
+        /// Supplied values are weighted by each asset's LTV for the borrow health, and by its liquidation threshold for the
+        /// liquidation health; both are returned as (borrow health, liquidation health).
+        fn __calculate_health(&mut self, supply_units: ValueMap, debt_units: ValueMap, fixed_debt: ValueMap) -> (Decimal, Decimal) {
+            // Return 'infinity' if no debt taken out
+            if debt_units.is_empty() && fixed_debt.is_empty() {
+                info!("[calculate_position_health] Health: Infinity {:?}", Decimal::MAX);
+
+                // Runtime::emit_event(PositionHealthEvent { health: Decimal::MAX });
+                return (Decimal::MAX, Decimal::MAX);
+            }
+
+            // Tick interest on all position assets and convert them from units to amounts
+            let mut updated_addresses: IndexSet<ResourceAddress> = IndexSet::new();
+
+            let supply: ValueMap = supply_units
+                .iter()
+                .map(|(&address, &unit_amount)| {
+                    if updated_addresses.insert(address) {
+                        self.assets.get_mut(&address).unwrap().cluster_wrapper.cluster.tick_interest(true);
+                    }
+
+                    let amount =
+                        self.assets
+                            .get(&address)
+                            .unwrap()
+                            .cluster_wrapper
+                            .cluster
+                            .get_amount(ClusterLayer::Supply, unit_amount, Rounding::Down);
+                    info!("Supply {:?}; units: {:?} -> amount: {:?}", address, unit_amount, amount);
+                    (address, amount)
+                })
+                .collect();
+
+            let mut debt: ValueMap = debt_units
+                .iter()
+                .map(|(&address, &unit_amount)| {
+                    if updated_addresses.insert(address) {
+                        self.assets.get_mut(&address).unwrap().cluster_wrapper.cluster.tick_interest(true);
+                    }
+
+                    let amount = self
+                        .assets
+                        .get(&address)
+                        .unwrap()
+                        .cluster_wrapper
+                        .cluster
+                        .get_amount(ClusterLayer::Debt, unit_amount, Rounding::Up);
+                    info!("Debt {:?}; units: {:?} -> amount: {:?}", address, unit_amount, amount);
+                    (address, amount)
+                })
+                .collect();
+
+            // Fixed-rate loans are already in terms of amounts
+            for (&address, &amount) in &fixed_debt {
+                let existing = debt.get(&address).copied().unwrap_or(dec!(0.0));
+                debt.insert(address, existing.checked_add(amount).unwrap());
+            }
+
+            // Calculate supply value, weighted by each asset's collateral parameters
+            let (supply_value, supply_values) = self.__get_asset_values(&supply);
+            info!("[calculate_position_health] Supply value: {}", supply_value);
+
+            let (borrow_capacity, liquidation_value) = self.__collateral_values(&supply_values);
+            info!(
+                "[calculate_position_health] Borrow capacity: {}, liquidation value: {}",
+                borrow_capacity, liquidation_value
+            );
+
+            // Calculate debt value
+            let (debt_value, _) = self.__get_asset_values(&debt);
+            info!("[calculate_position_health] Debt value: {}", debt_value);
+
+            // Sanity check
+            assert!(
+                debt_value > dec!(0.0),
+                "Debt value must be greater than 0, I don't know how we got here. Debt: {:?}",
+                debt
+            );
+
+            // health = (weighted supply / debt)
+            let borrow_health = borrow_capacity.checked_div(debt_value).unwrap();
+            let liquidation_health = liquidation_value.checked_div(debt_value).unwrap();
+            info!(
+                "[calculate_position_health] Borrow health: {:?}, liquidation health: {:?}",
+                borrow_health, liquidation_health
+            );
+
+            // Fire health event
+            // Runtime::emit_event(PositionHealthEvent { health });
+
+            (borrow_health, liquidation_health)
+        }
+
+        /// Weighs the USD values of supplied assets by their LTV and liquidation threshold, returning the borrowing
+        /// capacity and the value counted towards liquidation health
+        fn __collateral_values(&self, supply_values: &ValueMap) -> (Decimal, Decimal) {
+            let mut borrow_capacity = dec!(0.0);
+            let mut liquidation_value = dec!(0.0);
+
+            for (address, &value) in supply_values {
+                let entry = self.assets.get(address).unwrap();
+                borrow_capacity = borrow_capacity.checked_add(value.checked_mul(entry.ltv).unwrap()).unwrap();
+                liquidation_value = liquidation_value
+                    .checked_add(value.checked_mul(entry.liquidation_threshold).unwrap())
+                    .unwrap();
+            }
+
+            (borrow_capacity, liquidation_value)
+        }
+
+        /// Converts a position's supply, variable-rate debt and fixed-rate debt to amounts, including pending interest,
+        /// without ticking any cluster
+        fn __preview_position(&self, position_id: &NonFungibleLocalId) -> (ValueMap, ValueMap, ValueMap) {
+            let position: Position = self.position_manager.get_non_fungible_data(position_id);
+
+            let preview_amounts = |units: &ValueMap, layer: ClusterLayer, rounding: Rounding| -> ValueMap {
+                units
+                    .iter()
+                    .map(|(&address, &unit_amount)| {
+                        let cluster = self.assets.get(&address).unwrap().cluster_wrapper.cluster;
+                        (address, cluster.preview_amount(layer.clone(), unit_amount, rounding))
+                    })
+                    .collect()
+            };
+
+            let supply = preview_amounts(&position.supply, ClusterLayer::Supply, Rounding::Down);
+            let debt = preview_amounts(&position.debt, ClusterLayer::Debt, Rounding::Up);
+
+            // Fixed-rate loans accrue at their locked rate, which `get_fixed_debt` already accounts for
+            let fixed_debt: ValueMap = position
+                .fixed_debt
+                .iter()
+                .map(|(&address, loan_id)| {
+                    let cluster = self.assets.get(&address).unwrap().cluster_wrapper.cluster;
+                    (address, cluster.get_fixed_debt(loan_id.clone()))
+                })
+                .collect();
+
+            (supply, debt, fixed_debt)
+        }
+
+        /// Builds a `PositionPreview` from the amounts a position would hold
+        fn __preview(&self, supply: ValueMap, variable_debt: ValueMap, fixed_debt: ValueMap) -> PositionPreview {
+            let mut debt = variable_debt;
+            for (&address, &amount) in &fixed_debt {
+                add_to_value_map(&mut debt, address, amount);
+            }
+
+            let (_, supply_values) = self.__get_asset_values(&supply);
+            let (borrow_capacity, liquidation_value) = self.__collateral_values(&supply_values);
+            let (debt_value, _) = self.__get_asset_values(&debt);
+
+            // Return 'infinity' if no debt is owed
+            let (borrow_health, liquidation_health) = if debt_value > dec!(0.0) {
+                (
+                    borrow_capacity.checked_div(debt_value).unwrap(),
+                    liquidation_value.checked_div(debt_value).unwrap(),
+                )
+            } else {
+                (Decimal::MAX, Decimal::MAX)
+            };
+
+            PositionPreview {
+                supply,
+                debt,
+                borrow_capacity: borrow_capacity.checked_sub(debt_value).unwrap().max(dec!(0.0)),
+                borrow_health,
+                liquidation_health,
+            }
+        }
+
         /// Calculates the USD values of all provided asset from the oracle
         // TODO: provide epoch to ensure data not out-of-date
         fn __get_asset_values(&self, assets: &ValueMap) -> (Decimal, ValueMap) {
@@ -10,12 +10,48 @@ pub struct Position {
     pub supply: ValueMap, // Stored in terms of supply units
     #[mutable]
     pub debt: ValueMap, // Stored in terms of debt units
+    #[mutable]
+    pub fixed_debt: HashMap<ResourceAddress, NonFungibleLocalId>, // Fixed-rate loan held for each asset
+}
+
+/// State of a position after a previewed operation, as returned by the market's `preview_*` methods.
+#[derive(ScryptoSbor, Debug, Clone)]
+pub struct PositionPreview {
+    pub supply: ValueMap, // Supplied amounts, including pending interest
+    pub debt: ValueMap,   // Owed amounts, including pending interest and fixed-rate loans
+
+    pub borrow_capacity: Decimal,    // USD value which can still be borrowed against the supply
+    pub borrow_health: Decimal,      // Ratio of the borrowing capacity to the debt's value; borrowing requires at least 1.0
+    pub liquidation_health: Decimal, // The position can be liquidated below 1.0
+}
+
+/// Rate at which a position borrows; variable-rate debt is held as debt units, fixed-rate debt as a loan per asset.
+#[derive(ScryptoSbor, Debug, Clone, Copy, PartialEq)]
+pub enum BorrowMode {
+    Variable,
+    Fixed,
+}
+
+/// A single operation of a batch executed by the market's `position_execute`.
+///
+/// Supplied and repaid assets are taken from the batch's held resources: the buckets passed in, along with everything
+/// borrowed or withdrawn by earlier actions of the batch.
+#[derive(ScryptoSbor, Debug, Clone)]
+pub enum PositionAction {
+    /// Supplies an amount of a held asset; all of it if `None`.
+    Supply(ResourceAddress, Option<Decimal>),
+    /// Withdraws an amount of a supplied asset; all of it if `None`. See `position_withdraw`.
+    Withdraw(ResourceAddress, Option<Decimal>),
+    /// Borrows an amount of an asset at the given rate.
+    Borrow(ResourceAddress, Decimal, BorrowMode),
+    /// Repays debt at the given rate from a held asset, up to an amount; as much as is held or owed if `None`.
+    Repay(ResourceAddress, Option<Decimal>, BorrowMode),
 }
 
 impl Position {
     /// Initialises a new, empty `Position` struct.
     pub fn new() -> Self {
-        Position { supply: ValueMap::new(), debt: ValueMap::new() }
+        Position { supply: ValueMap::new(), debt: ValueMap::new(), fixed_debt: HashMap::new() }
     }
 
     /// Updates the supply of the position based on the given `ValueMap`.
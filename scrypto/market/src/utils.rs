@@ -7,11 +7,35 @@ pub type ValueMap = HashMap<ResourceAddress, Decimal>;
 pub type ValueUnitMap = HashMap<ResourceAddress, (Decimal, Decimal)>; // (amount, units corresponding to amount)
 pub type ValueTuple = (ResourceAddress, PreciseDecimal);
 
+/// Adds `delta` to the entry of `address` in `map`, removing the entry if it reaches zero
+pub fn add_to_value_map(map: &mut ValueMap, address: ResourceAddress, delta: Decimal) {
+    let amount = map.get(&address).copied().unwrap_or(dec!(0.0)).checked_add(delta).unwrap();
+
+    if amount == dec!(0.0) {
+        map.remove(&address);
+    } else {
+        map.insert(address, amount);
+    }
+}
+
 /* ---------- Mathematical Operations --------- */
 pub const ZERO_PRICE: Decimal = dec!(0.0001); // If price <= ZERO_PRICE, it will be considered zero
 
-pub fn trunc(precise: PreciseDecimal) -> Decimal {
-    return precise.checked_truncate(RoundingMode::ToZero).unwrap();
+/* ---------------- Liquidation --------------- */
+pub const DEFAULT_CLOSE_FACTOR: Decimal = dec!(0.5); // Share of a position's debt in an asset repayable per liquidation
+pub const DEFAULT_LIQUIDATION_BONUS: Decimal = dec!(0.05); // Share of the repaid value paid to liquidators on top
+pub const DEFAULT_AUCTION_START_BONUS: Decimal = dec!(0.01); // Bonus offered as a liquidation auction starts
+pub const DEFAULT_AUCTION_MAX_BONUS: Decimal = dec!(0.15); // Bonus offered once a liquidation auction has run its duration
+pub const DEFAULT_AUCTION_DURATION: i64 = 3_600; // Seconds for an auction's bonus to grow from its start to its max
+
+/* ---------------- Collateral ---------------- */
+pub const DEFAULT_LTV: Decimal = dec!(0.75); // Share of an asset's value which can be borrowed against
+pub const DEFAULT_LIQUIDATION_THRESHOLD: Decimal = dec!(0.8); // Share of an asset's value counted towards liquidation health
+
+/* ------------------- Time ------------------- */
+/// Returns the number of seconds since the Unix epoch, i.e. January 1st, 1970 00:00:00 UTC.
+pub fn now() -> i64 {
+    Clock::current_time(TimePrecisionV2::Second).seconds_since_unix_epoch
 }
 
 /* ------------------ LazyVec ----------------- */
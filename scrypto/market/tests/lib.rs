@@ -108,7 +108,7 @@ fn add_asset(ledger: &mut LedgerSimulator<NoExtension, InMemorySubstateDatabase>
     let manifest = ManifestBuilder::new()
         .lock_fee_from_faucet()
         .create_proof_from_account_of_amount(owner_account.address, owner_badge, dec!(1))
-        .call_method(component, "add_asset", manifest_args!(address))
+        .call_method(component, "add_asset", manifest_args!(address, None::<()>)) // Default cluster configuration
         .deposit_batch(owner_account.address)
         .build();
     let receipt = ledger.execute_manifest(manifest, vec![owner_account.nf_global_id()]);
@@ -185,7 +185,7 @@ fn asset_add_test() -> Result<(), RuntimeError> {
         .call_method(
             component,
             "add_asset",
-            manifest_args!(dummy_asset)
+            manifest_args!(dummy_asset, None::<()>)
         )
         .deposit_batch(main_account.address)
         .build();
@@ -220,7 +220,7 @@ fn asset_add_noperm_test() -> Result<(), RuntimeError> {
         .call_method(
             component,
             "add_asset",
-            manifest_args!(dummy_asset)
+            manifest_args!(dummy_asset, None::<()>)
         )
         .deposit_batch(user_account.address)
         .build();
@@ -251,7 +251,7 @@ fn asset_remove_test() -> Result<(), RuntimeError> {
             .call_method(
                 component,
                 "add_asset",
-                manifest_args!(dummy_asset)
+                manifest_args!(dummy_asset, None::<()>)
             )
             .deposit_batch(main_account.address)
             .build();
@@ -305,7 +305,7 @@ fn asset_remove_noperm_test() -> Result<(), RuntimeError> {
             .call_method(
                 component,
                 "add_asset",
-                manifest_args!(dummy_asset)
+                manifest_args!(dummy_asset, None::<()>)
             )
             .deposit_batch(main_account.address)
             .build();
@@ -362,3 +362,713 @@ fn asset_remove_invalid_test() -> () {
     log_tx("remove_asset:invalid", &receipt);
     receipt.expect_commit_failure();
 }
+
+/* ------------- Lending Helpers -------------- */
+// Packages referenced by the market's extern blueprints; published at these addresses in the simulator
+const CLUSTER_PACKAGE: &str = "package_tdx_2_1pkxjgnf0sc8cyv63fucee0eqmqjewnrnwqwnt4vwckwgpjwj844t8e";
+const PRICE_STREAM_PACKAGE: &str = "package_tdx_2_1p4ual4cc8tnvm93atjlp9q5ua3ae5l0xkgnd68mlqz6ehlr98qxr53";
+
+/// Mirror of the market's `BorrowMode`, encoded identically in manifests
+#[derive(ManifestSbor)]
+#[allow(dead_code)]
+enum BorrowMode {
+    Variable,
+    Fixed,
+}
+
+/// Mirror of the market's `LiquidationMode`, encoded identically in manifests
+#[derive(ManifestSbor)]
+#[allow(dead_code)]
+enum LiquidationMode {
+    FixedBonus,
+    Auction,
+}
+
+/// Mirror of the market's `PositionPreview`, decoded from preview outputs
+#[derive(ScryptoSbor, Debug)]
+struct PositionPreview {
+    supply: HashMap<ResourceAddress, Decimal>,
+    debt: HashMap<ResourceAddress, Decimal>,
+    borrow_capacity: Decimal,
+    borrow_health: Decimal,
+    liquidation_health: Decimal,
+}
+
+/// Mirror of the market's `PositionAction`, encoded identically in manifests
+#[derive(ManifestSbor)]
+#[allow(dead_code)]
+enum PositionAction {
+    Supply(ResourceAddress, Option<Decimal>),
+    Withdraw(ResourceAddress, Option<Decimal>),
+    Borrow(ResourceAddress, Decimal, BorrowMode),
+    Repay(ResourceAddress, Option<Decimal>, BorrowMode),
+}
+
+/// Publishes the package at `path` at the stokenet address the market's extern blueprints point to
+fn publish_extern(ledger: &mut LedgerSimulator<NoExtension, InMemorySubstateDatabase>, path: String, address: &str) {
+    let decoder = AddressBech32Decoder::new(&NetworkDefinition::stokenet());
+    let address = PackageAddress::try_from_bech32(&decoder, address).expect("Invalid package address");
+
+    ledger.compile_and_publish_at_address(path, address);
+}
+
+/// Initialise a market lending USD against XRD, both priced at 1; a lender supplies 10,000 USD as position #1#,
+/// and a borrower borrows 700 USD against 1,000 XRD as position #2#
+fn lending_setup() -> (
+    LedgerSimulator<NoExtension, InMemorySubstateDatabase>, // Ledger simulation
+    (ComponentAddress, ComponentAddress),                   // Lattic3, PriceStream
+    (Account, Account, Account),                            // Accounts: Main (lender), Borrower, Liquidator
+    (ResourceAddress, ResourceAddress),                     // Badges: Owner, Position
+    ResourceAddress,                                        // USD
+) {
+    //. Simulation Setup
+    let mut ledger: LedgerSimulator<NoExtension, InMemorySubstateDatabase> = LedgerSimulatorBuilder::new().build();
+
+    //. Account Setup
+    let mut accounts = (0..3).map(|_| {
+        let (public_key, private_key, address) = ledger.new_allocated_account();
+        Account { public_key, private_key, address }
+    });
+    let (main_account, borrower, liquidator) = (accounts.next().unwrap(), accounts.next().unwrap(), accounts.next().unwrap());
+
+    //. Resources
+    let owner_badge = create_fungible(&mut ledger, dec!(1), "Lattic3 Owner Badge", &main_account);
+    let usd = create_fungible(&mut ledger, dec!(100000), "USD", &main_account);
+
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .withdraw_from_account(main_account.address, usd, dec!(10000))
+        .try_deposit_entire_worktop_or_abort(liquidator.address, None)
+        .build();
+    ledger
+        .execute_manifest(manifest, vec![main_account.nf_global_id()])
+        .expect_commit_success();
+
+    //. Package Setup
+    publish_extern(&mut ledger, format!("{}/../cluster", this_package!()), CLUSTER_PACKAGE);
+    publish_extern(&mut ledger, format!("{}/../price-stream", this_package!()), PRICE_STREAM_PACKAGE);
+    let package_address = ledger.compile_and_publish(this_package!());
+
+    let price_stream_package =
+        PackageAddress::try_from_bech32(&AddressBech32Decoder::new(&NetworkDefinition::stokenet()), PRICE_STREAM_PACKAGE).unwrap();
+
+    //. Instantiate the price stream and the market, both owned by the owner badge
+    let mut instantiate = |package: PackageAddress, blueprint: &str| {
+        let manifest = ManifestBuilder::new()
+            .lock_fee_from_faucet()
+            .withdraw_from_account(main_account.address, owner_badge, dec!(1))
+            .take_from_worktop(owner_badge, dec!(1), "owner_badge")
+            .call_function_with_name_lookup(package, blueprint, "instantiate", |lookup| {
+                (main_account.address, lookup.bucket("owner_badge"))
+            })
+            .deposit_batch(main_account.address)
+            .build();
+        let receipt = ledger.execute_manifest(manifest, vec![main_account.nf_global_id()]);
+
+        log_tx("instantiate", &receipt);
+        let commit = receipt.expect_commit_success();
+        (commit.new_component_addresses()[0], commit.new_resource_addresses().clone())
+    };
+
+    let (price_stream, _) = instantiate(price_stream_package, "PriceStream");
+    let (market, market_resources) = instantiate(package_address, "Lattic3");
+    let position_badge = market_resources[1]; // Created after the admin badge
+
+    //. List XRD and USD
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(main_account.address, owner_badge, dec!(1))
+        .call_method(price_stream, "update_asset", manifest_args!(XRD, dec!(1))) // XRD is listed on instantiation
+        .call_method(price_stream, "add_asset", manifest_args!(usd, dec!(1)))
+        .call_method(market, "link_price_stream", manifest_args!(price_stream))
+        .build();
+    ledger
+        .execute_manifest(manifest, vec![main_account.nf_global_id()])
+        .expect_commit_success();
+
+    add_asset(&mut ledger, market, XRD, &main_account, owner_badge);
+    add_asset(&mut ledger, market, usd, &main_account, owner_badge);
+
+    //. Open positions
+    open_position(&mut ledger, market, &main_account, usd, dec!(10000));
+    open_position(&mut ledger, market, &borrower, XRD, dec!(1000));
+
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_non_fungibles(borrower.address, position_badge, [NonFungibleLocalId::integer(2)])
+        .pop_from_auth_zone("position_proof")
+        .call_method_with_name_lookup(market, "position_borrow", |lookup| {
+            (lookup.proof("position_proof"), HashMap::from([(usd, dec!(700))]), BorrowMode::Variable)
+        })
+        .deposit_batch(borrower.address)
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![borrower.nf_global_id()]);
+
+    log_tx("position_borrow", &receipt);
+    receipt.expect_commit_success();
+
+    //. Return
+    (
+        ledger,
+        (market, price_stream),
+        (main_account, borrower, liquidator),
+        (owner_badge, position_badge),
+        usd,
+    )
+}
+
+fn open_position(
+    ledger: &mut LedgerSimulator<NoExtension, InMemorySubstateDatabase>,
+    component: ComponentAddress,
+    account: &Account,
+    resource: ResourceAddress,
+    amount: Decimal,
+) {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .withdraw_from_account(account.address, resource, amount)
+        .take_from_worktop(resource, amount, "supply")
+        .call_method_with_name_lookup(component, "open_position", |lookup| (vec![lookup.bucket("supply")],))
+        .deposit_batch(account.address)
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![account.nf_global_id()]);
+
+    log_tx("open_position", &receipt);
+    receipt.expect_commit_success();
+}
+
+fn set_price(
+    ledger: &mut LedgerSimulator<NoExtension, InMemorySubstateDatabase>,
+    price_stream: ComponentAddress,
+    owner_account: &Account,
+    owner_badge: ResourceAddress,
+    asset: ResourceAddress,
+    price: Decimal,
+) {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(owner_account.address, owner_badge, dec!(1))
+        .call_method(price_stream, "update_asset", manifest_args!(asset, price))
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![owner_account.nf_global_id()]);
+
+    log_tx("update_asset", &receipt);
+    receipt.expect_commit_success();
+}
+
+fn get_position_health(
+    ledger: &mut LedgerSimulator<NoExtension, InMemorySubstateDatabase>,
+    component: ComponentAddress,
+    account: &Account,
+    position_badge: ResourceAddress,
+    position_id: u64,
+) -> (Decimal, Decimal) {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_non_fungibles(account.address, position_badge, [NonFungibleLocalId::integer(position_id)])
+        .pop_from_auth_zone("position_proof")
+        .call_method_with_name_lookup(component, "get_position_health", |lookup| (lookup.proof("position_proof"),))
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![account.nf_global_id()]);
+
+    log_tx("get_position_health", &receipt);
+    receipt.expect_commit_success().output(3)
+}
+
+fn liquidate_position(
+    ledger: &mut LedgerSimulator<NoExtension, InMemorySubstateDatabase>,
+    component: ComponentAddress,
+    liquidator: &Account,
+    position_id: u64,
+    (debt_asset, amount): (ResourceAddress, Decimal),
+    collateral_asset: ResourceAddress,
+) -> TransactionReceiptV1 {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .withdraw_from_account(liquidator.address, debt_asset, amount)
+        .take_from_worktop(debt_asset, amount, "repayment")
+        .call_method_with_name_lookup(component, "liquidate_position", |lookup| {
+            (NonFungibleLocalId::integer(position_id), lookup.bucket("repayment"), collateral_asset)
+        })
+        .deposit_batch(liquidator.address)
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![liquidator.nf_global_id()]);
+
+    log_tx("liquidate_position", &receipt);
+    receipt
+}
+
+/* ------------- Liquidation Tests ------------ */
+/// Tests that healthy positions cannot be liquidated
+#[test]
+fn liquidation_healthy_test() -> Result<(), RuntimeError> {
+    let (mut ledger, (market, _), (_, borrower, liquidator), (_, position_badge), usd) = lending_setup();
+
+    let (_, health) = get_position_health(&mut ledger, market, &borrower, position_badge, 2);
+    assert!(health >= dec!(1), "Position should start healthy, health: {}", health);
+
+    liquidate_position(&mut ledger, market, &liquidator, 2, (usd, dec!(100)), XRD).expect_commit_failure();
+
+    Ok(())
+}
+
+/// Tests that an unhealthy position is liquidated up to the close factor, paying the liquidator a bonus in collateral
+#[test]
+fn liquidation_test() -> Result<(), RuntimeError> {
+    let (mut ledger, (market, price_stream), (main_account, borrower, liquidator), (owner_badge, position_badge), usd) = lending_setup();
+
+    //. XRD drops to 0.6; the position's 600 USD of collateral no longer covers its 700 USD of debt
+    set_price(&mut ledger, price_stream, &main_account, owner_badge, XRD, dec!(0.6));
+
+    let (_, health) = get_position_health(&mut ledger, market, &borrower, position_badge, 2);
+    assert!(health < dec!(1), "Position should be unhealthy, health: {}", health);
+
+    //. Liquidating against an asset the position does not supply fails
+    liquidate_position(&mut ledger, market, &liquidator, 2, (usd, dec!(700)), usd).expect_commit_failure();
+
+    //. Repay as much as possible; only half of the debt (the default close factor) is taken
+    let xrd_before = ledger.get_component_balance(liquidator.address, XRD);
+    let usd_before = ledger.get_component_balance(liquidator.address, usd);
+
+    let receipt = liquidate_position(&mut ledger, market, &liquidator, 2, (usd, dec!(700)), XRD);
+    let commit = receipt.expect_commit_success();
+
+    assert!(
+        commit
+            .application_events
+            .iter()
+            .any(|(id, _)| ledger.event_name(id) == "LiquidationEvent"),
+        "No LiquidationEvent emitted"
+    );
+
+    let repaid = usd_before - ledger.get_component_balance(liquidator.address, usd);
+    let seized = ledger.get_component_balance(liquidator.address, XRD) - xrd_before;
+
+    assert!(
+        (repaid - dec!(350)).checked_abs().unwrap() < dec!(0.001),
+        "Repaid {} instead of half of the debt",
+        repaid
+    );
+
+    // 350 USD repaid, plus a 5% bonus, in XRD at 0.6
+    let expected = repaid * dec!(1.05) / dec!(0.6);
+    assert!(
+        (seized - expected).checked_abs().unwrap() < dec!(0.001),
+        "Seized {} XRD instead of {}",
+        seized,
+        expected
+    );
+
+    Ok(())
+}
+
+/// Tests that a full liquidation is limited by the position's collateral, after which its bad debt can be absorbed
+#[test]
+fn liquidation_full_test() -> Result<(), RuntimeError> {
+    let (mut ledger, (market, price_stream), (main_account, borrower, liquidator), (owner_badge, position_badge), usd) = lending_setup();
+
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(main_account.address, owner_badge, dec!(1))
+        .call_method(market, "set_close_factor", manifest_args!(dec!(1)))
+        .build();
+    ledger
+        .execute_manifest(manifest, vec![main_account.nf_global_id()])
+        .expect_commit_success();
+
+    //. XRD drops to 0.5; the 500 USD of collateral covers less than the debt plus the bonus
+    set_price(&mut ledger, price_stream, &main_account, owner_badge, XRD, dec!(0.5));
+    assert!(get_position_health(&mut ledger, market, &borrower, position_badge, 2).1 < dec!(1));
+
+    let xrd_before = ledger.get_component_balance(liquidator.address, XRD);
+    let usd_before = ledger.get_component_balance(liquidator.address, usd);
+
+    liquidate_position(&mut ledger, market, &liquidator, 2, (usd, dec!(700)), XRD).expect_commit_success();
+
+    let repaid = usd_before - ledger.get_component_balance(liquidator.address, usd);
+    let seized = ledger.get_component_balance(liquidator.address, XRD) - xrd_before;
+
+    // All collateral is seized, and only what it covers (500 USD, less the bonus) is repaid
+    assert!(
+        (seized - dec!(1000)).checked_abs().unwrap() < dec!(0.001),
+        "Seized {} XRD instead of all collateral",
+        seized
+    );
+    let expected = dec!(500) / dec!(1.05);
+    assert!(
+        (repaid - expected).checked_abs().unwrap() < dec!(0.001),
+        "Repaid {} instead of {}",
+        repaid,
+        expected
+    );
+
+    //. The remaining debt is no longer backed by collateral, and can be absorbed by the cluster
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(main_account.address, owner_badge, dec!(1))
+        .call_method(market, "absorb_bad_debt", manifest_args!(NonFungibleLocalId::integer(2), usd))
+        .build();
+    let receipt = ledger.execute_manifest(manifest, vec![main_account.nf_global_id()]);
+
+    log_tx("absorb_bad_debt", &receipt);
+    receipt.expect_commit_success();
+
+    Ok(())
+}
+
+/* ------------- Collateral Tests ------------- */
+/// Tests that supply is weighted by its LTV for borrowing and by its liquidation threshold for liquidation
+#[test]
+fn collateral_parameters_test() -> Result<(), RuntimeError> {
+    let (mut ledger, (market, _), (main_account, borrower, _), (owner_badge, position_badge), usd) = lending_setup();
+
+    // 1,000 XRD at 1 USD, with 700 USD borrowed; default LTV 0.75 and liquidation threshold 0.8
+    let (borrow_health, liquidation_health) = get_position_health(&mut ledger, market, &borrower, position_badge, 2);
+    assert!(
+        (borrow_health - dec!(750) / dec!(700)).checked_abs().unwrap() < dec!(0.0001),
+        "Unexpected borrow health: {}",
+        borrow_health
+    );
+    assert!(
+        (liquidation_health - dec!(800) / dec!(700)).checked_abs().unwrap() < dec!(0.0001),
+        "Unexpected liquidation health: {}",
+        liquidation_health
+    );
+
+    let borrow = |ledger: &mut LedgerSimulator<NoExtension, InMemorySubstateDatabase>, amount: Decimal| {
+        let manifest = ManifestBuilder::new()
+            .lock_fee_from_faucet()
+            .create_proof_from_account_of_non_fungibles(borrower.address, position_badge, [NonFungibleLocalId::integer(2)])
+            .pop_from_auth_zone("position_proof")
+            .call_method_with_name_lookup(market, "position_borrow", |lookup| {
+                (lookup.proof("position_proof"), HashMap::from([(usd, amount)]), BorrowMode::Variable)
+            })
+            .deposit_batch(borrower.address)
+            .build();
+        let receipt = ledger.execute_manifest(manifest, vec![borrower.nf_global_id()]);
+
+        log_tx("position_borrow", &receipt);
+        receipt
+    };
+
+    let set_collateral_parameters = |ledger: &mut LedgerSimulator<NoExtension, InMemorySubstateDatabase>, params: (Decimal, Decimal, Decimal)| {
+        let manifest = ManifestBuilder::new()
+            .lock_fee_from_faucet()
+            .create_proof_from_account_of_amount(main_account.address, owner_badge, dec!(1))
+            .call_method(market, "set_collateral_parameters", manifest_args!(XRD, params.0, params.1, params.2))
+            .build();
+        let receipt = ledger.execute_manifest(manifest, vec![main_account.nf_global_id()]);
+
+        log_tx("set_collateral_parameters", &receipt);
+        receipt
+    };
+
+    //. Borrowing beyond the LTV fails
+    borrow(&mut ledger, dec!(100)).expect_commit_failure();
+
+    //. Invalid parameters are rejected: LTV above the threshold, and a threshold and bonus exceeding 1 combined
+    set_collateral_parameters(&mut ledger, (dec!(0.9), dec!(0.85), dec!(0.05))).expect_commit_failure();
+    set_collateral_parameters(&mut ledger, (dec!(0.9), dec!(0.97), dec!(0.05))).expect_commit_failure();
+
+    //. Raising the LTV raises the borrowing capacity
+    set_collateral_parameters(&mut ledger, (dec!(0.9), dec!(0.95), dec!(0.05))).expect_commit_success();
+    borrow(&mut ledger, dec!(100)).expect_commit_success();
+
+    let (borrow_health, liquidation_health) = get_position_health(&mut ledger, market, &borrower, position_badge, 2);
+    assert!(
+        (borrow_health - dec!(900) / dec!(800)).checked_abs().unwrap() < dec!(0.0001),
+        "Unexpected borrow health: {}",
+        borrow_health
+    );
+    assert!(
+        (liquidation_health - dec!(950) / dec!(800)).checked_abs().unwrap() < dec!(0.0001),
+        "Unexpected liquidation health: {}",
+        liquidation_health
+    );
+
+    Ok(())
+}
+
+/* --------------- Auction Tests -------------- */
+/// Tests that auctioned positions are filled at a capped bonus growing over time, and that auctions end or expire
+#[test]
+fn liquidation_auction_test() -> Result<(), RuntimeError> {
+    let (mut ledger, (market, price_stream), (main_account, borrower, liquidator), (owner_badge, position_badge), usd) = lending_setup();
+
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(main_account.address, owner_badge, dec!(1))
+        .call_method(market, "set_liquidation_mode", manifest_args!(LiquidationMode::Auction))
+        .build();
+    ledger
+        .execute_manifest(manifest, vec![main_account.nf_global_id()])
+        .expect_commit_success();
+
+    let call_auction = |ledger: &mut LedgerSimulator<NoExtension, InMemorySubstateDatabase>, method: &str| {
+        let manifest = ManifestBuilder::new()
+            .lock_fee_from_faucet()
+            .call_method(market, method, manifest_args!(NonFungibleLocalId::integer(2)))
+            .build();
+        let receipt = ledger.execute_manifest(manifest, vec![liquidator.nf_global_id()]);
+
+        log_tx(method, &receipt);
+        receipt
+    };
+
+    //. Healthy positions cannot be auctioned
+    call_auction(&mut ledger, "start_auction").expect_commit_failure();
+
+    set_price(&mut ledger, price_stream, &main_account, owner_badge, XRD, dec!(0.6));
+
+    //. Unhealthy positions cannot be liquidated until auctioned
+    liquidate_position(&mut ledger, market, &liquidator, 2, (usd, dec!(100)), XRD).expect_commit_failure();
+
+    let start = ledger.get_current_time(TimePrecisionV2::Second).seconds_since_unix_epoch;
+    call_auction(&mut ledger, "start_auction").expect_commit_success();
+    call_auction(&mut ledger, "start_auction").expect_commit_failure();
+
+    // Fills 100 USD of the position's debt, returning the XRD seized
+    let fill = |ledger: &mut LedgerSimulator<NoExtension, InMemorySubstateDatabase>| {
+        let xrd_before = ledger.get_component_balance(liquidator.address, XRD);
+        liquidate_position(ledger, market, &liquidator, 2, (usd, dec!(100)), XRD).expect_commit_success();
+        ledger.get_component_balance(liquidator.address, XRD) - xrd_before
+    };
+
+    //. At the start of the auction, the bonus is the start bonus (1%)
+    let seized = fill(&mut ledger);
+    let expected = dec!(100) * dec!(1.01) / dec!(0.6);
+    assert!(
+        (seized - expected).checked_abs().unwrap() < dec!(0.001),
+        "Seized {} XRD instead of {}",
+        seized,
+        expected
+    );
+
+    //. Half way through the auction, the bonus is half way to the max bonus (8%)
+    ledger.advance_to_round_at_timestamp(Round::of(2), (start + 1_800) * 1_000);
+
+    let seized = fill(&mut ledger);
+    let expected = dec!(100) * dec!(1.08) / dec!(0.6);
+    assert!(
+        (seized - expected).checked_abs().unwrap() < dec!(0.001),
+        "Seized {} XRD instead of {}",
+        seized,
+        expected
+    );
+
+    //. The auction cannot be ended while the position is unhealthy, but can be once it has recovered
+    call_auction(&mut ledger, "end_auction").expect_commit_failure();
+
+    set_price(&mut ledger, price_stream, &main_account, owner_badge, XRD, dec!(1));
+    assert!(get_position_health(&mut ledger, market, &borrower, position_badge, 2).1 >= dec!(1));
+
+    let receipt = call_auction(&mut ledger, "end_auction");
+    let commit = receipt.expect_commit_success();
+    assert!(
+        commit.application_events.iter().any(|(id, _)| ledger.event_name(id) == "AuctionEndEvent"),
+        "No AuctionEndEvent emitted"
+    );
+
+    //. With a liquidation threshold of 0.9, the bonus is capped at 1 / 0.9 - 1 (~11.1%) instead of the max bonus (15%)
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(main_account.address, owner_badge, dec!(1))
+        .call_method(
+            market,
+            "set_collateral_parameters",
+            manifest_args!(XRD, dec!(0.75), dec!(0.9), dec!(0.05)),
+        )
+        .build();
+    ledger
+        .execute_manifest(manifest, vec![main_account.nf_global_id()])
+        .expect_commit_success();
+
+    set_price(&mut ledger, price_stream, &main_account, owner_badge, XRD, dec!(0.7));
+
+    let start = ledger.get_current_time(TimePrecisionV2::Second).seconds_since_unix_epoch;
+    call_auction(&mut ledger, "start_auction").expect_commit_success();
+    ledger.advance_to_round_at_timestamp(Round::of(3), (start + 3_600) * 1_000);
+
+    let seized = fill(&mut ledger);
+    let expected = dec!(100) / dec!(0.9) / dec!(0.7);
+    assert!(
+        (seized - expected).checked_abs().unwrap() < dec!(0.001),
+        "Seized {} XRD instead of {}",
+        seized,
+        expected
+    );
+
+    //. Past its duration, the auction expires; it cannot be filled, and is restarted at the start bonus
+    ledger.advance_to_round_at_timestamp(Round::of(4), (start + 3_601) * 1_000);
+    liquidate_position(&mut ledger, market, &liquidator, 2, (usd, dec!(100)), XRD).expect_commit_failure();
+
+    call_auction(&mut ledger, "start_auction").expect_commit_success();
+
+    let seized = fill(&mut ledger);
+    let expected = dec!(100) * dec!(1.01) / dec!(0.7);
+    assert!(
+        (seized - expected).checked_abs().unwrap() < dec!(0.001),
+        "Seized {} XRD instead of {}",
+        seized,
+        expected
+    );
+
+    Ok(())
+}
+
+/* -------------- Preview Tests --------------- */
+/// Tests that previews include pending interest, and reflect each operation without requiring the position badge
+#[test]
+fn preview_test() -> Result<(), RuntimeError> {
+    let (mut ledger, (market, _), (_, _, liquidator), _, usd) = lending_setup();
+
+    // Previews are called by an account which does not own the position
+    let preview = |ledger: &mut LedgerSimulator<NoExtension, InMemorySubstateDatabase>, method: &str, args: ManifestArgs| -> PositionPreview {
+        let manifest = ManifestBuilder::new().lock_fee_from_faucet().call_method(market, method, args).build();
+        let receipt = ledger.execute_manifest(manifest, vec![liquidator.nf_global_id()]);
+
+        log_tx(method, &receipt);
+        receipt.expect_commit_success().output(1)
+    };
+    let position_id = NonFungibleLocalId::integer(2);
+    let within = |a: Decimal, b: Decimal| (a - b).checked_abs().unwrap() < dec!(0.0001);
+
+    //. Borrowing up to the LTV (0.75 of 1,000 XRD at 1 USD) uses the entire borrowing capacity
+    let result = preview(
+        &mut ledger,
+        "preview_borrow",
+        manifest_args!(position_id.clone(), HashMap::from([(usd, dec!(50))]), BorrowMode::Variable),
+    );
+    assert!(within(result.debt[&usd], dec!(750)), "Unexpected debt: {:?}", result.debt);
+    assert!(
+        within(result.borrow_health, dec!(1)),
+        "Unexpected borrow health: {}",
+        result.borrow_health
+    );
+    assert!(
+        within(result.borrow_capacity, dec!(0)),
+        "Unexpected borrow capacity: {}",
+        result.borrow_capacity
+    );
+
+    //. Withdrawing lowers the supply and the health
+    let result = preview(&mut ledger, "preview_withdraw", manifest_args!(position_id.clone(), XRD, Some(dec!(100))));
+    assert!(within(result.supply[&XRD], dec!(900)), "Unexpected supply: {:?}", result.supply);
+    assert!(
+        within(result.borrow_health, dec!(675) / dec!(700)),
+        "Unexpected borrow health: {}",
+        result.borrow_health
+    );
+
+    //. Repaying all debt leaves the position without debt
+    let result = preview(
+        &mut ledger,
+        "preview_repay",
+        manifest_args!(position_id.clone(), usd, dec!(1000), BorrowMode::Variable),
+    );
+    assert!(result.debt.is_empty(), "Debt remains: {:?}", result.debt);
+    assert_eq!(result.liquidation_health, Decimal::MAX);
+
+    //. Supplying to a new position
+    let result = preview(
+        &mut ledger,
+        "preview_supply",
+        manifest_args!(None::<NonFungibleLocalId>, HashMap::from([(usd, dec!(100))])),
+    );
+    assert!(
+        within(result.borrow_capacity, dec!(75)),
+        "Unexpected borrow capacity: {}",
+        result.borrow_capacity
+    );
+
+    //. After a year without ticks, the previewed debt includes the pending interest
+    let start = ledger.get_current_time(TimePrecisionV2::Second).seconds_since_unix_epoch;
+    ledger.advance_to_round_at_timestamp(Round::of(2), (start + 31_557_600) * 1_000);
+
+    let result = preview(&mut ledger, "preview_withdraw", manifest_args!(position_id.clone(), XRD, Some(dec!(1))));
+    assert!(result.debt[&usd] > dec!(700), "Pending interest not included: {:?}", result.debt);
+
+    Ok(())
+}
+
+/* ----------- Batched Action Tests ----------- */
+/// Tests that batched actions are checked for health once, after all actions are applied
+#[test]
+fn position_execute_test() -> Result<(), RuntimeError> {
+    let (mut ledger, (market, _), (_, borrower, _), (_, position_badge), usd) = lending_setup();
+
+    let execute = |ledger: &mut LedgerSimulator<NoExtension, InMemorySubstateDatabase>, actions: Vec<PositionAction>, repayment: Decimal| {
+        let manifest = ManifestBuilder::new()
+            .lock_fee_from_faucet()
+            .withdraw_from_account(borrower.address, usd, repayment)
+            .take_all_from_worktop(usd, "repayment")
+            .create_proof_from_account_of_non_fungibles(borrower.address, position_badge, [NonFungibleLocalId::integer(2)])
+            .pop_from_auth_zone("position_proof")
+            .call_method_with_name_lookup(market, "position_execute", |lookup| {
+                (lookup.proof("position_proof"), actions, vec![lookup.bucket("repayment")])
+            })
+            .deposit_batch(borrower.address)
+            .build();
+        let receipt = ledger.execute_manifest(manifest, vec![borrower.nf_global_id()]);
+
+        log_tx("position_execute", &receipt);
+        receipt
+    };
+
+    //. Withdrawing 100 XRD on its own exceeds the borrowing capacity (0.75 of 900 XRD < 700 USD)
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_non_fungibles(borrower.address, position_badge, [NonFungibleLocalId::integer(2)])
+        .pop_from_auth_zone("position_proof")
+        .call_method_with_name_lookup(market, "position_withdraw", |lookup| (lookup.proof("position_proof"), XRD, Some(dec!(100))))
+        .deposit_batch(borrower.address)
+        .build();
+    ledger.execute_manifest(manifest, vec![borrower.nf_global_id()]).expect_commit_failure();
+
+    //. A batch ending unhealthy is rejected
+    execute(
+        &mut ledger,
+        vec![
+            PositionAction::Withdraw(XRD, Some(dec!(200))),
+            PositionAction::Repay(usd, Some(dec!(100)), BorrowMode::Variable),
+        ],
+        dec!(100),
+    )
+    .expect_commit_failure();
+
+    //. Withdrawing 100 XRD then repaying 100 USD ends healthy, even though the intermediate state is not
+    let xrd_before = ledger.get_component_balance(borrower.address, XRD);
+    let usd_before = ledger.get_component_balance(borrower.address, usd);
+
+    execute(
+        &mut ledger,
+        vec![
+            PositionAction::Withdraw(XRD, Some(dec!(100))),
+            PositionAction::Repay(usd, None, BorrowMode::Variable),
+        ],
+        dec!(100),
+    )
+    .expect_commit_success();
+
+    let withdrawn = ledger.get_component_balance(borrower.address, XRD) - xrd_before;
+    let repaid = usd_before - ledger.get_component_balance(borrower.address, usd);
+    assert!(
+        (withdrawn - dec!(100)).checked_abs().unwrap() < dec!(0.001),
+        "Withdrew {} XRD instead of 100",
+        withdrawn
+    );
+    assert!(
+        (repaid - dec!(100)).checked_abs().unwrap() < dec!(0.001),
+        "Repaid {} USD instead of 100",
+        repaid
+    );
+
+    let (borrow_health, _) = get_position_health(&mut ledger, market, &borrower, position_badge, 2);
+    assert!(
+        (borrow_health - dec!(675) / dec!(600)).checked_abs().unwrap() < dec!(0.0001),
+        "Unexpected borrow health: {}",
+        borrow_health
+    );
+
+    Ok(())
+}